@@ -0,0 +1,119 @@
+/**
+ * @module muses_experimenter.js
+ * @author Benedict R. Gaster
+ * @copyright University of West of England, 2019
+ * 
+ * web-socket API for the experimenter view of a muses survey session.
+ * 
+ * The experimenter follows the session (current slide, input activity and 
+ * results) and can pause, skip, restart or end it.
+ * 
+ * See README.md for more details on the protocol between view/server.
+ *
+ * @license: See LICENCE
+ */
+
+//------------------------------------------------------------------------------
+
+var ws;
+
+// index of slide running, whose live results are shown
+var currentSlide = -1;
+
+(function(window) {
+    if ("WebSocket" in window) {
+        console.log("Initializing Muses Experimenter...")
+
+        ws = new WebSocket('ws://localhost:8080');
+
+        ws.onopen = function() {
+            sendMS({type: "connected", role: "experimenter"});
+        }
+
+        /**
+         * handle messages from Muses survey server
+         */
+        ws.onmessage = function (evt) {
+            var data = JSON.parse(evt.data);
+            if (data.type == "status") {
+              // live results are only for the slide running
+              if (data.index != currentSlide || data.state != "running") {
+                showLiveStats([]);
+              }
+              currentSlide = data.index;
+              document.getElementById("participant").innerHTML = data.participant;
+              document.getElementById("slide-index").innerHTML = data.index + 1;
+              document.getElementById("slide-total").innerHTML = data.total;
+              document.getElementById("slide-name").innerHTML = data.slide;
+              document.getElementById("slide-state").innerHTML = data.paused ? "paused" : data.state;
+            }
+            else if (data.type == "sessionEnded") {
+              document.getElementById("session-ended").innerHTML = "(session ended)";
+            }
+            else if (data.type == "inputActivity") {
+              showInput(data.rates, data.last, data.unmatched);
+            }
+            else if (data.type == "trialStats") {
+              showTrialStats(data.summary);
+            }
+            else if (data.type == "liveStats") {
+              showLiveStats(data.stats);
+            }
+            else if (data.type == "participant") {
+              document.getElementById("participant-message").innerHTML = 
+                JSON.stringify(data.message);
+            }
+        };
+
+        ws.onclose = function() {
+            // websocket is closed.
+        }
+    }
+})(window);
+
+// send message to server, we assume it can be converted to JSON
+function sendMS(data) {
+  ws.send(JSON.stringify(data));
+}
+
+//------------------------------------------------------------------------------
+
+function sendControl(action) {
+  sendMS({type: "control", action: action});
+}
+
+function showInput(rates, last, unmatched) {
+  document.getElementById("input-rates").innerHTML = rates
+    .map(function(r) { return "Material " + r.material + ": " + r.rate.toFixed(1) + " msgs/s"; })
+    .join("<br>");
+
+  if (last) {
+    document.getElementById("input-last").innerHTML = 
+      "Last: " + last.input + " material " + last.material + 
+      " pressure " + last.pressure.toFixed(1) + 
+      " (" + last.x.toFixed(1) + ", " + last.y.toFixed(1) + ")";
+  }
+
+  document.getElementById("input-unmatched").innerHTML = 
+    "Unmatched OSC messages: " + (unmatched || 0);
+}
+
+function showLiveStats(stats) {
+  var table = document.getElementById("live-stats");
+  table.innerHTML = "";
+  stats.forEach(function(s) {
+    var row = table.insertRow();
+    row.insertCell().innerHTML = s.label;
+    row.insertCell().innerHTML = s.value;
+  });
+}
+
+function showTrialStats(summary) {
+  var table = document.getElementById("trial-stats");
+  table.innerHTML = "";
+  summary.forEach(function(s) {
+    var row = table.insertRow();
+    row.insertCell().innerHTML = s.label;
+    row.insertCell().innerHTML = s.value;
+  });
+}
@@ -22,6 +22,8 @@ const likertSlideNumber = 2;
 const pressSlideNumber  = 3;
 const sliderSlideNumber = 8;
 const tapSlideNumber    = 9;
+const target2DSlideNumber = 10;
+const holdSlideNumber   = 11;
 
 // Globals for slider animation
 var box_x = 20.0;
@@ -32,6 +34,13 @@ var box_size = 150.0;
 var tap_arrow_x = 100.0;
 var tap_user_x = 20.0;
 
+// Globals for 2D target animation
+var target2d_cursor_x = 375.0;
+var target2d_cursor_y = 200.0;
+var target2d_x = 375.0;
+var target2d_y = 200.0;
+var target2d_size = 60.0;
+
 
 (function(window) {
     /**
@@ -48,7 +57,7 @@ var tap_user_x = 20.0;
             // Web Socket is connected, send data using send()
             
             // send connected message to confirm start of protocol.
-            sendMS({type: "connected"});
+            sendMS({type: "connected", role: "participant"});
         }
 
         /**
@@ -58,10 +67,20 @@ var tap_user_x = 20.0;
             console.log(evt.data);
 
             var data = JSON.parse(evt.data);
+
+            // frames are acknowledged once drawn, so server can measure latency
+            if (data.seq !== undefined) {
+              frameDrawn(data.seq, performance.now());
+            }
+
             if (data.type == "press") {
               // draw press circle and ring
               updatePressCircle(data.circle, data.ring);
             }
+            else if (data.type == "hold") {
+              // draw hold circle and band
+              updateHold(data.circle, data.band_inner, data.band_outer, data.progress);
+            }
             else if (data.type == "slider") {
               // draw slider canvas
               user_x = data.user_x;
@@ -76,9 +95,73 @@ var tap_user_x = 20.0;
               tap_arrow_x  = data.arrow_x;
               
             }
+            else if (data.type == "target2D") {
+              // update 2D target canvas globals
+              target2d_cursor_x = data.cursor_x;
+              target2d_cursor_y = data.cursor_y;
+              target2d_x = data.target_x;
+              target2d_y = data.target_y;
+              target2d_size = data.target_size;
+            }
+            else if (data.type == "likertDefinition") {
+              // build likert items for next likert slide
+              createLikert(data.kind, data.items, data.scale, data.anchors);
+            }
+            else if (data.type == "likertInvalid") {
+              document.getElementById("likert-error").innerHTML = data.reason;
+            }
+            else if (data.type == "likertIncomplete") {
+              document.getElementById("likert-error").innerHTML = 
+                "Please answer: " + data.missing.join(", ");
+            }
+            else if (data.type == "responseSelection") {
+              showResponse("Selected: " + data.materials.join(", ") + 
+                " (" + data.materials.length + " of " + data.required + ")");
+            }
+            else if (data.type == "responseRejected") {
+              showResponse("Material " + data.material + " not selected: " + data.reason);
+            }
+            else if (data.type == "pairwise") {
+              // set materials being compared
+              document.getElementById("pairwiseAttribute").innerHTML = data.attribute;
+              document.getElementById("pairwiseLeft").innerHTML = data.left;
+              document.getElementById("pairwiseRight").innerHTML = data.right;
+              document.getElementById("pairwiseTrial").innerHTML = data.trial;
+              document.getElementById("pairwiseTotal").innerHTML = data.total;
+            }
+            else if (data.type == "formSchema") {
+              createForm(data.title, data.fields);
+            }
+            else if (data.type == "formErrors") {
+              showFormErrors(data.errors);
+            }
+            else if (data.type == "tlxPair") {
+              showTlxPair(data.left, data.right, data.trial, data.total);
+            }
+            else if (data.type == "textPrompt") {
+              document.getElementById("textPrompt").innerHTML = data.prompt;
+              document.getElementById("textResponse").value = "";
+              document.getElementById("text-error").innerHTML = "";
+              if (data.max_length) {
+                document.getElementById("textResponse").maxLength = data.max_length;
+              }
+              else {
+                document.getElementById("textResponse").removeAttribute("maxlength");
+              }
+            }
+            else if (data.type == "textInvalid") {
+              document.getElementById("text-error").innerHTML = data.reason;
+            }
+            else if (data.type == "closing") {
+              showClosing(data.debrief, data.summary);
+            }
             else if (data.type == "consentID") {
               document.getElementById("consent-id").innerHTML = data.id;
             }
+            else if (data.type == "consentText") {
+              document.getElementById("consent-text").innerHTML = data.text;
+              document.getElementById("consent-version").innerHTML = data.version;
+            }
             else if (data.type == "materialIndex") {
                 // set current material index
                 if (data.slide == likertSlideNumber) {
@@ -95,6 +178,13 @@ var tap_user_x = 20.0;
                   document.getElementById("materialIndexTap").innerHTML = data.value;
                   window.requestAnimationFrame(updateTap);
                 }
+                else if (data.slide == holdSlideNumber) {
+                  document.getElementById("materialIndexHold").innerHTML = data.value;
+                }
+                else if (data.slide == target2DSlideNumber) {
+                  document.getElementById("materialIndexTarget2D").innerHTML = data.value;
+                  window.requestAnimationFrame(updateTarget2D);
+                }
             }
             else if (data.type == "gestureType") {
               // set current gesture type
@@ -118,6 +208,13 @@ function sendMS(data) {
   ws.send(JSON.stringify(data));
 }
 
+// acknowledge frame seq, after next repaint has drawn it
+function frameDrawn(seq, received) {
+  window.requestAnimationFrame(function () {
+    sendMS({type: "frameDrawn", seq: seq, drawMs: performance.now() - received});
+  });
+}
+
 
 //------------------------------------------------------------------------------
 // UTILS
@@ -138,6 +235,70 @@ function submitRadios() {
   }
 }
 
+// names of items on current likert slide
+var likertItems = [];
+// visual analogue scale items the participant has moved, only these are answered
+var movedRanges = {};
+
+// create rated items, on the given kind of scale:
+//   likert       - a radio button for each point on scale, labelled by first and last anchor
+//   differential - as likert, but labelled by each item's left and right anchor
+//   vas          - a continuous slider from 0 to scale, labelled by first and last anchor
+function createLikert(kind, items, scale, anchors) {
+  likertItems = items.map(function(item) { return item.name; });
+  movedRanges = {};
+  document.getElementById("likert-error").innerHTML = "";
+
+  var html = "";
+  for (var i=0; i < items.length; i++) {
+    // items may have their own anchors, e.g. differential or NASA-TLX subscales
+    var left  = items[i].left ? items[i].left : anchors[0];
+    var right = items[i].right ? items[i].right : anchors[anchors.length-1];
+
+    html += '<ul class="likert">';
+    html += '<li> ' + items[i].text + '&nbsp; <small style="vertical-align: middle; margin: 0px;">' + 
+              left + '&nbsp;</small> </li>';
+    if (kind == "vas") {
+      html += '<li><input type="range" min="0" max="' + scale + '" name="' + items[i].name + 
+                '" oninput="moveRange(this.name)" onchange="moveRange(this.name); submitRating(this.name, this.value)" /></li>';
+    }
+    else {
+      for (var value=1; value <= scale; value++) {
+        var title = kind == "likert" ? anchors[value-1] : value;
+        html += '<li><input type="radio" name="' + items[i].name + '" value="' + value + 
+                  '" title="' + title + '" onchange="submitRating(this.name, this.value)" /></li>';
+      }
+    }
+    html += '<li> <small style="vertical-align: middle; margin: 0px;" >&nbsp;' + 
+              right + '</small> </li>';
+    html += '</ul>';
+  }
+  document.getElementById("likert-items").innerHTML = html;
+}
+
+// send answer for a single rated item
+function submitRating(name, value) {
+  sendMS({type: "likert", name: name, value: parseInt(value, 10)});
+}
+
+// visual analogue scale item has been moved by participant
+function moveRange(name) {
+  movedRanges[name] = true;
+}
+
+// answers are sent as they are changed, so submission just confirms them. The
+// value of each visual analogue scale item that has been moved is sent again, in 
+// case its last change was missed. Items never moved are left unanswered
+function submitLikertItems() {
+  var ranges = document.querySelectorAll('#likert-items input[type="range"]');
+  for (var i = 0; i < ranges.length; i++) {
+    if (movedRanges[ranges[i].name]) {
+      submitRating(ranges[i].name, ranges[i].value);
+    }
+  }
+  sendMS({type: "likertConfirm"});
+}
+
 function submitLikert(likert) { 
   var ele = document.getElementsByName(likert); 
   for(i = 0; i < ele.length; i++) { 
@@ -147,6 +308,110 @@ function submitLikert(likert) {
   } 
 }
 
+//------------------------------------------------------------------------------
+// NASA-TLX weights
+//------------------------------------------------------------------------------
+
+// subscales of current pair
+var tlxLeft = "";
+var tlxRight = "";
+
+function showTlxPair(left, right, trial, total) {
+  tlxLeft = left.name;
+  tlxRight = right.name;
+  document.getElementById("tlxLeft").innerHTML = left.text.split(":")[0];
+  document.getElementById("tlxRight").innerHTML = right.text.split(":")[0];
+  document.getElementById("tlxTrial").innerHTML = trial;
+  document.getElementById("tlxTotal").innerHTML = total;
+}
+
+function sendTlxChoice(left) {
+  sendMS({type: "tlxChoice", choice: left ? tlxLeft : tlxRight});
+}
+
+//------------------------------------------------------------------------------
+// Free text
+//------------------------------------------------------------------------------
+
+function submitText() {
+  sendMS({type: "text", value: document.getElementById("textResponse").value});
+}
+
+//------------------------------------------------------------------------------
+// FORMS
+//------------------------------------------------------------------------------
+
+// fields of current form
+var formFields = [];
+
+// create form from schema, each field has a name, label, kind and possibly options
+function createForm(title, fields) {
+  formFields = fields;
+  document.getElementById("formTitle").innerHTML = title;
+
+  var html = "";
+  for (var i=0; i < fields.length; i++) {
+    var f = fields[i];
+    html += '<div><label>' + f.label + (f.required ? ' *' : '') + '</label> ';
+    if (f.kind == "text") {
+      html += '<input type="text" name="' + f.name + '"' + 
+                (f.max_length ? ' maxlength="' + f.max_length + '"' : '') + ' />';
+    }
+    else if (f.kind == "number") {
+      html += '<input type="number" name="' + f.name + '"' + 
+                (f.min != undefined ? ' min="' + f.min + '"' : '') + 
+                (f.max != undefined ? ' max="' + f.max + '"' : '') + 
+                (f.integer ? ' step="1"' : ' step="any"') + ' />';
+    }
+    else {
+      var type = f.kind == "single" ? "radio" : "checkbox";
+      for (var j=0; j < f.options.length; j++) {
+        html += '<label><input type="' + type + '" name="' + f.name + '" value="' + 
+                  f.options[j] + '" /> ' + f.options[j] + '</label> ';
+      }
+    }
+    html += ' <small class="form-error" id="form-error-' + f.name + '"></small></div>';
+  }
+  document.getElementById("form-fields").innerHTML = html;
+}
+
+// send values of all fields, validation happens on the server
+function submitForm() {
+  var values = {};
+  for (var i=0; i < formFields.length; i++) {
+    var f = formFields[i];
+    var eles = document.getElementsByName(f.name);
+    if (f.kind == "single") {
+      values[f.name] = null;
+      for (var j=0; j < eles.length; j++) {
+        if (eles[j].checked) {
+          values[f.name] = eles[j].value;
+        }
+      }
+    }
+    else if (f.kind == "multi") {
+      values[f.name] = [];
+      for (var j=0; j < eles.length; j++) {
+        if (eles[j].checked) {
+          values[f.name].push(eles[j].value);
+        }
+      }
+    }
+    else {
+      values[f.name] = eles[0].value;
+    }
+  }
+  sendMS({type: "form", values: values});
+}
+
+// show error for each invalid field
+function showFormErrors(errors) {
+  for (var i=0; i < formFields.length; i++) {
+    var name = formFields[i].name;
+    document.getElementById("form-error-" + name).innerHTML = errors[name] ? errors[name] : "";
+  }
+}
+
 //------------------------------------------------------------------------------
 // Send begin message
 //------------------------------------------------------------------------------
@@ -163,6 +428,45 @@ function sendConsent() {
   sendMS({"type": "consent"});
 }
 
+function sendDecline() {
+  sendMS({"type": "decline"});
+}
+
+//------------------------------------------------------------------------------
+// Responses
+//------------------------------------------------------------------------------
+
+// show selection on each response slide
+function showResponse(text) {
+  var eles = document.getElementsByClassName("response-selection");
+  for (var i=0; i < eles.length; i++) {
+    eles[i].innerHTML = text;
+  }
+}
+
+function sendResponseUndo() {
+  sendMS({"type": "responseUndo"});
+}
+
+function sendResponseConfirm() {
+  sendMS({"type": "responseConfirm"});
+}
+
+//------------------------------------------------------------------------------
+// Closing
+//------------------------------------------------------------------------------
+
+// show debrief and summary of results, each with a label and value
+function showClosing(debrief, summary) {
+  document.getElementById("closing-debrief").innerHTML = debrief;
+
+  var html = "";
+  for (var i=0; i < summary.length; i++) {
+    html += '<tr><td>' + summary[i].label + '</td><td>' + summary[i].value + '</td></tr>';
+  }
+  document.getElementById("closing-summary").innerHTML = html;
+}
+
 //------------------------------------------------------------------------------
 // canvas for pressCircle 
 //------------------------------------------------------------------------------
@@ -191,6 +495,42 @@ function updatePressCircle(circleRadius, ringRadius) {
   contextPressCircle.stroke();   
 }
 
+//------------------------------------------------------------------------------
+// canvas for hold 
+//------------------------------------------------------------------------------
+
+var canvasHold    = document.getElementById('holdCanvas');
+canvasHold.width  = 400;
+canvasHold.height = 400;
+var contextHold = canvasHold.getContext('2d');
+
+// draw hold band, circle and progress of hold
+function updateHold(circleRadius, bandInner, bandOuter, progress) {
+  // clear canvas
+  contextHold.clearRect(0, 0, 400, 400);
+
+  // draw band
+  contextHold.globalAlpha = 0.5;
+  contextHold.beginPath();
+  contextHold.arc(200, 200, bandOuter, 0, 2 * Math.PI);
+  contextHold.arc(200, 200, bandInner, 0, 2 * Math.PI, true);
+  contextHold.fillStyle = '#F3B73B';
+  contextHold.fill();
+  contextHold.globalAlpha = 1.0;
+
+  // draw solid circle
+  contextHold.beginPath();
+  contextHold.arc(200, 200, circleRadius, 0, 2 * Math.PI);
+  contextHold.fillStyle = '#D989BC';
+  contextHold.fill();
+
+  // draw progress of hold around the outside
+  contextHold.beginPath();
+  contextHold.lineWidth = 5;
+  contextHold.arc(200, 200, 190, -Math.PI / 2, -Math.PI / 2 + progress * 2 * Math.PI);
+  contextHold.stroke();
+}
+
 //------------------------------------------------------------------------------
 // canvas for slider 
 //------------------------------------------------------------------------------
@@ -309,4 +649,41 @@ function updateTap() {
   // contextTap.globalAlpha = 1.0;
 
   window.requestAnimationFrame(updateTap);
-}
\ No newline at end of file
+}
+
+//------------------------------------------------------------------------------
+// canvas for 2D target 
+//------------------------------------------------------------------------------
+
+var canvasTarget2D    = document.getElementById('target2DCanvas');
+canvasTarget2D.width  = 750;
+canvasTarget2D.height = 400;
+var contextTarget2D = canvasTarget2D.getContext('2d');
+
+// draw target and cursor
+function updateTarget2D() {
+  // clear canvas
+  contextTarget2D.clearRect(0, 0, 750, 400);
+
+  // draw border of pad area
+  contextTarget2D.beginPath();
+  contextTarget2D.rect(20, 20, 710, 360);
+  contextTarget2D.lineWidth = 2;
+  contextTarget2D.stroke();
+
+  // draw target
+  contextTarget2D.globalAlpha = 0.5;
+  contextTarget2D.beginPath();
+  contextTarget2D.arc(target2d_x, target2d_y, target2d_size / 2, 0, 2 * Math.PI);
+  contextTarget2D.fillStyle = '#F3B73B';
+  contextTarget2D.fill();
+  contextTarget2D.globalAlpha = 1.0;
+
+  // draw cursor
+  contextTarget2D.beginPath();
+  contextTarget2D.arc(target2d_cursor_x, target2d_cursor_y, 10, 0, 2 * Math.PI);
+  contextTarget2D.fillStyle = '#D989BC';
+  contextTarget2D.fill();
+
+  window.requestAnimationFrame(updateTarget2D);
+}
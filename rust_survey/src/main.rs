@@ -18,9 +18,12 @@ use rand::Rng;
 extern crate uuid;
 use uuid::Uuid;
 
-use std::sync::mpsc::{channel, Sender, Receiver};
+use std::sync::mpsc::{channel, Sender, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use std::fs::{write, File};
 use std::io::Read;
@@ -33,6 +36,12 @@ mod msg;
 mod ws_server;
 mod slide;
 mod world;
+mod metrics;
+mod survey;
+mod runtime;
+mod task;
+#[cfg(feature = "tui")]
+mod tui;
 
 use crate::msg::*;
 
@@ -48,6 +57,39 @@ struct Config {
     id: u32,
     csv: String,
     likert_dir: String,
+    /// directory for participant metadata
+    #[serde(default = "Config::default_participants_dir")]
+    participants_dir: String,
+    /// survey definition
+    #[serde(default = "Config::default_survey")]
+    survey: String,
+    /// frame rate (Hz) of animated slides
+    #[serde(default = "Config::default_frame_rate")]
+    frame_rate: f32,
+    /// mappings from OSC messages onto touches
+    #[serde(default = "Config::default_osc_input")]
+    osc_input: Vec<osc_device::Mapping>,
+    /// TUIO surface, with regions that are materials
+    #[serde(default)]
+    tuio: Option<osc_device::TuioConfig>,
+}
+
+impl Config {
+    fn default_survey() -> String {
+        "./assets/survey.json".to_string()
+    }
+
+    fn default_participants_dir() -> String {
+        "./assets/data/participants/".to_string()
+    }
+
+    fn default_frame_rate() -> f32 {
+        60.0
+    }
+
+    fn default_osc_input() -> Vec<osc_device::Mapping> {
+        vec![osc_device::Mapping::material()]
+    }
 }
 
 fn main() {
@@ -67,8 +109,46 @@ fn main() {
     f.read_to_string(&mut config).expect("Unable to read config file");
     std::mem::drop(f);
 
-    // Deserialize config
-    let mut config : Config  = serde_json::from_str(&config).expect("Invalid config file");
+    // Deserialize config, keeping file as written so defaults are not added to it
+    let mut config_file : serde_json::Value = serde_json::from_str(&config).expect("Invalid config file");
+    let mut config : Config  = serde_json::from_value(config_file.clone()).expect("Invalid config file");
+    if !(config.frame_rate > 0.0) {
+        panic!("Invalid config file, frame_rate must be greater than 0");
+    }
+    if config.osc_input.is_empty() {
+        panic!("Invalid config file, osc_input must have at least one mapping");
+    }
+    if let Some(Err(e)) = config.tuio.as_ref().map(|tuio| tuio.check()) {
+        panic!("Invalid config file, {}", e);
+    }
+
+    // withdraw command deletes all data for a participant, rather than running a survey
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 3 && args[1] == "withdraw" {
+        match world::withdraw(&config.likert_dir, &config.participants_dir, &args[2]) {
+            Ok(removed) => {
+                println!("withdrew participant {}, removed {} file(s)", args[2], removed.len());
+                for path in removed {
+                    println!("  {}", path);
+                }
+            },
+            Err(e) => {
+                eprintln!("unable to withdraw participant: {}", e);
+                std::process::exit(1);
+            },
+        }
+        return;
+    }
+
+    // tui command runs survey with a terminal UI for the experimenter
+    let tui_mode = args.len() == 2 && args[1] == "tui";
+    if tui_mode && !cfg!(feature = "tui") {
+        println!("terminal UI not available, build with --features tui");
+        return;
+    }
+
+    // Read survey definition, i.e. the slides to be presented
+    let slides = survey::load(&config.survey).expect("Invalid survey definition");
 
     let id = Uuid::new_v4();
     let likert_cvs = format!("{}{}.csv", config.likert_dir.clone(), id.to_hyphenated().to_string());
@@ -76,17 +156,19 @@ fn main() {
         .write(true)
         //.append(true)
         .create(true)
-        .open(likert_cvs)
+        .open(likert_cvs.clone())
         .unwrap();
 
     // add a new line to the CSV file, just to make sure we start on a new line
     //writeln!(file, "\n");
 
-    let mut world = world::World::new(id, likert_file);
+    let metadata_path = format!("{}{}.json", config.participants_dir.clone(), id.to_hyphenated().to_string());
+    let mut world = world::World::new(id, likert_file, likert_cvs, metadata_path);
 
     // update id to next free and update config file
     config.id = config.id + 1;
-    let j = serde_json::to_string(&config).unwrap();
+    config_file["id"] = json!(config.id);
+    let j = serde_json::to_string(&config_file).unwrap();
     write(config_path, j).expect("Unable to write file");
 
     //println!("filename {}", config.csv);
@@ -94,12 +176,17 @@ fn main() {
     // setup OSC thread....
 
     // create commincation channel for server
-    let (osc_s, osc_r)    = channel();
-
-    let osc_thread = std::thread::Builder::new()
+    let (osc_s, osc_input)    = channel();
+
+    let osc_input_mappings = config.osc_input.clone();
+    let osc_tuio = config.tuio.clone();
+    // OSC messages that match no input, reported to experimenters
+    let unmatched = Arc::new(AtomicU64::new(0));
+    let osc_unmatched = unmatched.clone();
+    let _osc_thread = std::thread::Builder::new()
             .spawn(move || {
                 info!("osc thread is running");
-                let osc = osc_device::OSCBuilder::new(osc_s)
+                let osc = osc_device::OSCBuilder::new(osc_s, osc_input_mappings, osc_tuio, osc_unmatched)
                     .select_port()
                     .finish();
 
@@ -110,61 +197,130 @@ fn main() {
 
     // Now we setup up webserver which handles the event loop
 
-    // channel to receive messages from web-client
-    let (inbound, receive_incoming_msgs)  : (Sender<Message>, Receiver<Message>) = channel();
+    // channel to receive messages from web-client, along with OSC input, as events for slides
+    let (inbound, receive_events)  : (Sender<Event>, Receiver<Event>) = channel();
 
     // webserver handles the connection between backend and web-client
     // note: the listener is assumed not be on the main thread and thus does not 
     // block us 
     let ws = ws_server::WSServer::new("127.0.0.1:8080".to_string(), inbound.clone());
+    let events = runtime::Events::new(receive_events, config.frame_rate, ws.monitor());
+
+    // OSC input is passed on to slides via monitor, so experimenters can see input activity
+    let monitor_s = inbound.clone();
+    let monitor = ws.monitor();
+    std::thread::spawn(move || {
+        monitor_input(osc_input, monitor_s, monitor, unmatched);
+    });
+
+    #[cfg(feature = "tui")]
+    let tui = if tui_mode {
+        Some(tui::Tui::start(
+            world.create_id(),
+            slides.iter().map(|entry| entry.name.to_string()).collect(),
+            ws.monitor().observe(),
+            inbound.clone()))
+    }
+    else {
+        None
+    };
+
+    // wait for connection message from client, so we know protocol has begun, 
+    // the session can be aborted before then
+    loop {
+        match events.recv(None) {
+            Ok(Event::Message(data)) => {
+                if is_connected(data.clone()) {
+                    break;
+                }
+                if control(data) == Ok(Control::Abort) {
+                    world.ended = true;
+                    break;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    let mut index = 0;
+    while index < slides.len() {
+        // session may end early, e.g. if consent is declined
+        if world.ended {
+            break;
+        }
+
+        let entry = &slides[index];
+        ws.send_experimenter(msg::status(world.create_id(), index, slides.len(), entry.name, "running"));
+
+        let start = Instant::now();
+        let deadline = entry.timeout.map(|timeout| start + timeout);
+        events.start_slide();
+        let outcome = entry.slide.run(&mut world, &events, &ws, deadline);
+
+        // nothing is recorded if participant's data has been discarded
+        if !world.ended {
+            world.writeOutcome(index, entry.name, outcome.name(), start.elapsed().as_millis());
+        }
+        info!("slide {} ({}) {}", index, entry.name, outcome.name());
+        ws.send_experimenter(msg::status(world.create_id(), index, slides.len(), entry.name, outcome.name()));
+        ws.send_experimenter(msg::trialStats(&world.summary));
+
+        match outcome {
+            slide::Outcome::Repeated => {},
+            slide::Outcome::Aborted => world.ended = true,
+            _ => index = index + 1,
+        }
+    }
+
+    ws.send_experimenter(msg::sessionEnded(world.create_id()));
 
-    // wait for connection message from client, so we know protocol has begun
-    match receive_incoming_msgs.recv() {
-        Ok(data) => {
-            if !is_connected(data) {
-                println!("bad connecition message");
-                return;
-            }
-        },
-        _ => {},
+    #[cfg(feature = "tui")]
+    {
+        if let Some(tui) = tui {
+            tui.finish();
+        }
     }
+}
 
-    let mut slides: Vec<Box<slide::Slide>> = vec![
-        Box::new(slide::FrontMatter::new()),
-        //Box::new(slide::Consent::new()),
-        Box::new(slide::Tap::new(1, 20, 2.0, 30.0, 30.0, 30.0, 30.0)),
-        //Box::new(slide::Press::new(1, 20, 2.0)),
-    ];
-
-    // let mut slides: Vec<Box<slide::Slide>> = vec![
-    //     Box::new(slide::FrontMatter::new()),
-    //     Box::new(slide::Consent::new()),
-    //     Box::new(slide::Likert::new(1, "Tap".to_string())),
-    //     Box::new(slide::Likert::new(1, "Press".to_string())),
-    //     Box::new(slide::Likert::new(1, "Slider".to_string())),
-    //     Box::new(slide::Likert::new(2, "Tap".to_string())),
-    //     Box::new(slide::Likert::new(2, "Press".to_string())),
-    //     Box::new(slide::Likert::new(2, "Slider".to_string())),
-    //     Box::new(slide::Likert::new(3, "Tap".to_string())),
-    //     Box::new(slide::Likert::new(3, "Press".to_string())),
-    //     Box::new(slide::Likert::new(3, "Slider".to_string())),
-    
-    //     //Box::new(slide::Press::new(1, 10)),
-    // ];
-
-
-    // let mut slides: Vec<Box<slide::Slide>> = vec![
-    //     Box::new(slide::FrontMatter::new()),
-    //     Box::new(slide::Consent::new()),
-    //     Box::new(slide::Likert::new(3, "TAP".to_string())),
-    //     Box::new(slide::Response::new("most_accurate".to_string(), 1, most_accurate_num())),
-    //     Box::new(slide::Response::new("most_comfortable".to_string(), 1, most_comfortable_num())),
-    //     Box::new(slide::Response::new("most_responsive".to_string(), 1, most_responsive_num())),
-    //     Box::new(slide::Response::new("order_favorite".to_string(), 4, order_favorite_num())),
-    //     Box::new(slide::Press::new(1, 10)),
-    // ];
-
-    for slide in slides {
-        slide.run(&mut world, &osc_r, &ws, &receive_incoming_msgs);
+/// how often input activity is reported to experimenters
+const INPUT_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// forward OSC input to slides, periodically reporting the rate of input for each 
+/// material, along with the last input and number of unmatched OSC messages, to 
+/// experimenters
+fn monitor_input(
+    input: Receiver<(SenselMessage, Instant)>, 
+    output: Sender<Event>, 
+    monitor: ws_server::Monitor,
+    unmatched: Arc<AtomicU64>) {
+    let mut counts: HashMap<u32, u32> = HashMap::new();
+    let mut last: Option<SenselMessage> = None;
+    let mut timer = Instant::now();
+
+    loop {
+        match input.recv_timeout(INPUT_REPORT_INTERVAL) {
+            Ok((message, received)) => {
+                *counts.entry(message.4).or_insert(0) += 1;
+                last = Some(message);
+                if output.send(Event::Input(message, received)).is_err() {
+                    return;
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        if timer.elapsed() >= INPUT_REPORT_INTERVAL {
+            let seconds = timer.elapsed().as_secs_f32();
+            let mut rates: Vec<(u32, f32)> = counts
+                .iter()
+                .map(|(material, count)| (*material, *count as f32 / seconds))
+                .collect();
+            rates.sort_by_key(|(material, _)| *material);
+
+            monitor.send(msg::inputActivity(&rates, last.as_ref(), unmatched.load(Ordering::Relaxed)));
+            counts.clear();
+            timer = Instant::now();
+        }
     }
 }
@@ -0,0 +1,471 @@
+//! Performance metrics for continuous gesture tasks
+//!
+//! Copyright: Benedict R. Gaster
+//!
+//!
+
+//-----------------------------------------------------------------------------
+// Tracking error
+//-----------------------------------------------------------------------------
+
+/// Online accumulator of tracking error between a user cursor and a target.
+///
+/// Error terms are updated per sample, the cursor and target series are also
+/// kept so that lag can be estimated by cross-correlation at the end of a trial.
+pub struct Tracking {
+    /// number of samples seen
+    samples: u32,
+    /// sum of squared error
+    sum_sq: f32,
+    /// sum of absolute error
+    sum_abs: f32,
+    /// total time tracked (seconds)
+    time_total: f32,
+    /// time cursor was on target (seconds)
+    time_on_target: f32,
+    /// number of times cursor left target
+    exits: u32,
+    /// was cursor on target at last sample
+    on_target: bool,
+    /// cursor and target x positions, one entry per sample
+    series_x: Vec<(f32, f32)>,
+    /// cursor and target y positions, one entry per sample (empty for 1D tracking)
+    series_y: Vec<(f32, f32)>,
+}
+
+impl Tracking {
+    /// maximum lag, in samples, considered when estimating lag
+    const MAX_LAG: usize = 60;
+
+    pub fn new() -> Self {
+        Tracking {
+            samples: 0,
+            sum_sq: 0.0,
+            sum_abs: 0.0,
+            time_total: 0.0,
+            time_on_target: 0.0,
+            exits: 0,
+            on_target: false,
+            series_x: Vec::new(),
+            series_y: Vec::new(),
+        }
+    }
+
+    /// add a 1D sample, where dt is time (in seconds) since the previous sample
+    pub fn update(&mut self, cursor: f32, target: f32, on_target: bool, dt: f32) {
+        self.add(cursor - target, on_target, dt);
+        self.series_x.push((cursor, target));
+    }
+
+    /// add a 2D sample, error is the euclidean distance between cursor and target
+    pub fn update_2d(&mut self, cursor: (f32, f32), target: (f32, f32), on_target: bool, dt: f32) {
+        let dx = cursor.0 - target.0;
+        let dy = cursor.1 - target.1;
+        self.add((dx * dx + dy * dy).sqrt(), on_target, dt);
+        self.series_x.push((cursor.0, target.0));
+        self.series_y.push((cursor.1, target.1));
+    }
+
+    fn add(&mut self, error: f32, on_target: bool, dt: f32) {
+        self.samples = self.samples + 1;
+        self.sum_sq  = self.sum_sq + error * error;
+        self.sum_abs = self.sum_abs + error.abs();
+
+        self.time_total = self.time_total + dt;
+        if on_target {
+            self.time_on_target = self.time_on_target + dt;
+        }
+        else if self.on_target {
+            self.exits = self.exits + 1;
+        }
+        self.on_target = on_target;
+    }
+
+    /// root mean square error
+    pub fn rms(&self) -> f32 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        (self.sum_sq / self.samples as f32).sqrt()
+    }
+
+    /// mean absolute error
+    pub fn mean_abs(&self) -> f32 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        self.sum_abs / self.samples as f32
+    }
+
+    /// percentage of tracked time cursor was on target
+    pub fn percent_on_target(&self) -> f32 {
+        if self.time_total <= 0.0 {
+            return 0.0;
+        }
+        100.0 * self.time_on_target / self.time_total
+    }
+
+    /// number of times cursor exited target
+    pub fn exits(&self) -> u32 {
+        self.exits
+    }
+
+    /// lag (ms) of cursor behind target, estimated by cross-correlation. For 2D
+    /// tracking this is the mean of the lag on each axis.
+    pub fn lag_ms(&self) -> f32 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+
+        let lag = |series: &Vec<(f32, f32)>| {
+            let (cursor, target): (Vec<f32>, Vec<f32>) = series.iter().cloned().unzip();
+            cross_correlation_lag(&target, &cursor, Tracking::MAX_LAG) as f32
+        };
+
+        let lag = if self.series_y.is_empty() {
+            lag(&self.series_x)
+        }
+        else {
+            (lag(&self.series_x) + lag(&self.series_y)) / 2.0
+        };
+
+        // samples are assumed to be evenly spaced
+        let interval = self.time_total / self.samples as f32;
+        lag * interval * 1000.0
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Utils
+//-----------------------------------------------------------------------------
+
+/// returns the lag k, in 0..=max_lag samples, which maximizes the correlation of
+/// input[t] with reference[t-k]
+pub fn cross_correlation_lag(reference: &[f32], input: &[f32], max_lag: usize) -> usize {
+    let n = usize::min(reference.len(), input.len());
+    if n < 2 {
+        return 0;
+    }
+
+    let mean = |v: &[f32]| v.iter().sum::<f32>() / v.len() as f32;
+    let reference_mean = mean(&reference[..n]);
+    let input_mean = mean(&input[..n]);
+
+    let mut best_lag = 0;
+    let mut best_correlation = std::f32::MIN;
+
+    for k in 0..=usize::min(max_lag, n - 2) {
+        let mut sum  = 0.0;
+        let mut ref_sq = 0.0;
+        let mut input_sq = 0.0;
+        for t in k..n {
+            let r = reference[t - k] - reference_mean;
+            let i = input[t] - input_mean;
+            sum = sum + r * i;
+            ref_sq = ref_sq + r * r;
+            input_sq = input_sq + i * i;
+        }
+
+        let denominator = (ref_sq * input_sq).sqrt();
+        if denominator > 0.0 && sum / denominator > best_correlation {
+            best_correlation = sum / denominator;
+            best_lag = k;
+        }
+    }
+
+    best_lag
+}
+
+//-----------------------------------------------------------------------------
+// Fitts' law
+//-----------------------------------------------------------------------------
+
+/// A single Fitts' law selection
+#[derive(Clone, Copy, Debug)]
+pub struct FittsTrial {
+    /// nominal distance to target
+    pub distance: f32,
+    /// nominal width of target
+    pub width: f32,
+    /// deviation of endpoint from target centre, along the task axis
+    pub deviation: f32,
+    /// selection time (ms)
+    pub time: u128,
+    /// was the endpoint within the target
+    pub hit: bool,
+}
+
+/// A single selection, along with where it came from and ended up
+pub struct FittsSelection {
+    pub trial: FittsTrial,
+    /// centre of previous target
+    pub from: (f32, f32),
+    /// centre of target
+    pub target: (f32, f32),
+    /// cursor position at selection
+    pub endpoint: (f32, f32),
+}
+
+/// Effective measures for a set of Fitts' law selections
+#[derive(Clone, Copy, Debug)]
+pub struct FittsSummary {
+    /// number of selections
+    pub trials: usize,
+    /// percentage of selections that missed target
+    pub error_rate: f32,
+    /// effective distance
+    pub effective_distance: f32,
+    /// effective width (4.133 x standard deviation of endpoints)
+    pub effective_width: f32,
+    /// effective index of difficulty (bits)
+    pub effective_id: f32,
+    /// mean movement time (ms)
+    pub movement_time: f32,
+    /// throughput (bits/s)
+    pub throughput: f32,
+}
+
+impl FittsSummary {
+    /// summarise selections, in general these should share distance and width
+    pub fn new(trials: &[FittsTrial]) -> Self {
+        let n = trials.len() as f32;
+        if trials.is_empty() {
+            return FittsSummary {
+                trials: 0,
+                error_rate: 0.0,
+                effective_distance: 0.0,
+                effective_width: 0.0,
+                effective_id: 0.0,
+                movement_time: 0.0,
+                throughput: 0.0,
+            };
+        }
+
+        let misses = trials.iter().filter(|t| !t.hit).count() as f32;
+        let effective_distance = trials.iter().map(|t| t.distance + t.deviation).sum::<f32>() / n;
+        let mean_deviation = trials.iter().map(|t| t.deviation).sum::<f32>() / n;
+        let variance = if trials.len() > 1 {
+            trials.iter().map(|t| (t.deviation - mean_deviation).powi(2)).sum::<f32>() / (n - 1.0)
+        }
+        else {
+            0.0
+        };
+        let effective_width = 4.133 * variance.sqrt();
+
+        // without spread in endpoints effective width is undefined, so fall back to nominal width
+        let width = if effective_width > 0.0 { effective_width } else { trials[0].width };
+        let effective_id = (effective_distance / width + 1.0).log2();
+        let movement_time = trials.iter().map(|t| t.time as f32).sum::<f32>() / n;
+
+        FittsSummary {
+            trials: trials.len(),
+            error_rate: 100.0 * misses / n,
+            effective_distance: effective_distance,
+            effective_width: effective_width,
+            effective_id: effective_id,
+            movement_time: movement_time,
+            throughput: if movement_time > 0.0 { effective_id / (movement_time / 1000.0) } else { 0.0 },
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Steadiness
+//-----------------------------------------------------------------------------
+
+/// Steadiness of a value held at a target over a window
+#[derive(Clone, Copy, Debug)]
+pub struct Steadiness {
+    /// number of samples in window
+    pub samples: usize,
+    /// mean absolute error from target
+    pub mean_error: f32,
+    /// coefficient of variation (standard deviation / mean)
+    pub cv: f32,
+    /// slope of least squares fit of value against time (units per second)
+    pub drift: f32,
+}
+
+impl Steadiness {
+    /// samples are (time in seconds, value)
+    pub fn new(target: f32, samples: &[(f32, f32)]) -> Self {
+        let n = samples.len() as f32;
+        if samples.is_empty() {
+            return Steadiness { samples: 0, mean_error: 0.0, cv: 0.0, drift: 0.0 };
+        }
+
+        let mean_time = samples.iter().map(|s| s.0).sum::<f32>() / n;
+        let mean = samples.iter().map(|s| s.1).sum::<f32>() / n;
+        let mean_error = samples.iter().map(|s| (s.1 - target).abs()).sum::<f32>() / n;
+        let variance = samples.iter().map(|s| (s.1 - mean).powi(2)).sum::<f32>() / n;
+
+        let covariance = samples.iter().map(|s| (s.0 - mean_time) * (s.1 - mean)).sum::<f32>();
+        let time_variance = samples.iter().map(|s| (s.0 - mean_time).powi(2)).sum::<f32>();
+
+        Steadiness {
+            samples: samples.len(),
+            mean_error: mean_error,
+            cv: if mean != 0.0 { variance.sqrt() / mean } else { 0.0 },
+            drift: if time_variance > 0.0 { covariance / time_variance } else { 0.0 },
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Workload
+//-----------------------------------------------------------------------------
+
+/// NASA-TLX workload, from ratings (0 to 100) of each subscale
+#[derive(Clone, Copy, Debug)]
+pub struct Workload {
+    /// mean of ratings
+    pub raw: f32,
+    /// mean of ratings weighted by the number of times each subscale was chosen in 
+    /// the pairwise comparisons, if they were made
+    pub weighted: Option<f32>,
+}
+
+impl Workload {
+    pub fn new(ratings: &[u32], weights: Option<&Vec<u32>>) -> Self {
+        let raw = if ratings.is_empty() { 0.0 } else { ratings.iter().sum::<u32>() as f32 / ratings.len() as f32 };
+        let weighted = weights
+            .filter(|weights| weights.iter().sum::<u32>() > 0)
+            .map(|weights| {
+                let total: u32 = weights.iter().sum();
+                let sum: u32 = ratings.iter().zip(weights).map(|(r, w)| r * w).sum();
+                sum as f32 / total as f32
+            });
+
+        Workload {
+            raw: raw,
+            weighted: weighted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_correlation_finds_lag() {
+        let reference: Vec<f32> = (0..100).map(|t| (t as f32 * 0.2).sin()).collect();
+        let input: Vec<f32> = (0..100).map(|t| if t < 5 { 0.0 } else { reference[t - 5] }).collect();
+        assert_eq!(cross_correlation_lag(&reference, &input, 20), 5);
+        assert_eq!(cross_correlation_lag(&reference, &reference, 20), 0);
+    }
+
+    #[test]
+    fn cross_correlation_too_short() {
+        assert_eq!(cross_correlation_lag(&[1.0], &[1.0], 10), 0);
+        assert_eq!(cross_correlation_lag(&[], &[], 10), 0);
+    }
+
+    #[test]
+    fn tracking_error() {
+        let mut tracking = Tracking::new();
+        tracking.update(1.0, 0.0, true, 0.5);
+        tracking.update(-3.0, 0.0, false, 0.5);
+        tracking.update(0.0, 0.0, true, 1.0);
+        tracking.update(2.0, 0.0, false, 1.0);
+
+        assert_eq!(tracking.mean_abs(), 1.5);
+        assert!((tracking.rms() - (14.0f32 / 4.0).sqrt()).abs() < 1e-6);
+        assert_eq!(tracking.percent_on_target(), 50.0);
+        assert_eq!(tracking.exits(), 2);
+    }
+
+    #[test]
+    fn tracking_error_2d() {
+        let mut tracking = Tracking::new();
+        tracking.update_2d((3.0, 4.0), (0.0, 0.0), false, 1.0);
+        assert_eq!(tracking.mean_abs(), 5.0);
+        assert_eq!(tracking.exits(), 0);
+    }
+
+    fn fitts_trial(deviation: f32, time: u128) -> FittsTrial {
+        FittsTrial { distance: 100.0, width: 10.0, deviation: deviation, time: time, hit: deviation.abs() <= 5.0 }
+    }
+
+    #[test]
+    fn fitts_effective_measures() {
+        let trials = vec![fitts_trial(-2.0, 400), fitts_trial(2.0, 600), fitts_trial(6.0, 500), fitts_trial(-6.0, 500)];
+        let summary = FittsSummary::new(&trials);
+
+        // endpoints -2, 2, 6, -6 have standard deviation sqrt(80 / 3)
+        let effective_width = 4.133 * (80.0f32 / 3.0).sqrt();
+        assert_eq!(summary.trials, 4);
+        assert_eq!(summary.error_rate, 50.0);
+        assert_eq!(summary.effective_distance, 100.0);
+        assert!((summary.effective_width - effective_width).abs() < 1e-3);
+        assert_eq!(summary.movement_time, 500.0);
+        let effective_id = (100.0 / effective_width + 1.0).log2();
+        assert!((summary.effective_id - effective_id).abs() < 1e-4);
+        assert!((summary.throughput - effective_id / 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fitts_without_spread_uses_nominal_width() {
+        let summary = FittsSummary::new(&[fitts_trial(0.0, 1000)]);
+        assert_eq!(summary.effective_width, 0.0);
+        assert!((summary.effective_id - (100.0f32 / 10.0 + 1.0).log2()).abs() < 1e-6);
+        assert!((summary.throughput - summary.effective_id).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fitts_without_selections() {
+        let summary = FittsSummary::new(&[]);
+        assert_eq!(summary.trials, 0);
+        assert_eq!(summary.throughput, 0.0);
+    }
+
+    #[test]
+    fn steadiness_of_constant_hold() {
+        let samples: Vec<(f32, f32)> = (0..10).map(|t| (t as f32 * 0.1, 500.0)).collect();
+        let steadiness = Steadiness::new(520.0, &samples);
+        assert_eq!(steadiness.samples, 10);
+        assert!((steadiness.mean_error - 20.0).abs() < 1e-3);
+        assert_eq!(steadiness.cv, 0.0);
+        assert!(steadiness.drift.abs() < 1e-3);
+    }
+
+    #[test]
+    fn steadiness_drift() {
+        // pressure rises 50 per second, around 500
+        let samples: Vec<(f32, f32)> = (0..11).map(|t| (t as f32 * 0.1, 475.0 + t as f32 * 5.0)).collect();
+        let steadiness = Steadiness::new(500.0, &samples);
+        assert!((steadiness.drift - 50.0).abs() < 1e-2);
+        assert!(steadiness.cv > 0.0);
+    }
+
+    #[test]
+    fn workload_raw_and_weighted() {
+        let ratings = vec![80, 20, 50, 10, 60, 30];
+        let raw = Workload::new(&ratings, None);
+        assert_eq!(raw.raw, 250.0 / 6.0);
+        assert_eq!(raw.weighted, None);
+
+        // 15 pairwise choices, weighted score is sum of rating * weight over 15
+        let weights = vec![5, 0, 3, 1, 4, 2];
+        let weighted = Workload::new(&ratings, Some(&weights));
+        assert_eq!(weighted.raw, 250.0 / 6.0);
+        assert_eq!(weighted.weighted, Some((400 + 150 + 10 + 240 + 60) as f32 / 15.0));
+
+        assert_eq!(Workload::new(&ratings, Some(&vec![0; 6])).weighted, None);
+    }
+
+    #[test]
+    fn steadiness_without_samples() {
+        let steadiness = Steadiness::new(500.0, &[]);
+        assert_eq!(steadiness.samples, 0);
+        assert_eq!(steadiness.drift, 0.0);
+    }
+
+    #[test]
+    fn tracking_without_samples() {
+        let tracking = Tracking::new();
+        assert_eq!(tracking.rms(), 0.0);
+        assert_eq!(tracking.percent_on_target(), 0.0);
+        assert_eq!(tracking.lag_ms(), 0.0);
+    }
+}
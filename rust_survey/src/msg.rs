@@ -8,6 +8,10 @@ use serde::Deserialize;
 use serde_json::json;
 use serde_json::{Value};
 
+use std::time::Instant;
+
+use crate::slide::{LikertItem, FormField};
+
 //-----------------------------------------------------------------------
 // Message type
 //-----------------------------------------------------------------------
@@ -15,7 +19,7 @@ use serde_json::{Value};
 pub type Message = serde_json::Value; 
 
 /// Input type of touch on sensel, i.e start touch, move, and end touch
-#[derive(PartialEq,Debug)]
+#[derive(PartialEq,Debug,Clone,Copy)]
 pub enum InputType {
     Start,
     Move,
@@ -39,8 +43,25 @@ impl InputType {
     }
 }
 
-// (InputType, pressure, x, y, material)
-pub type SenselMessage = (InputType, f32, f32, f32, u32);
+/// When a touch was sampled by the sensor and received by the host, both in ms 
+/// since the Unix epoch
+#[derive(PartialEq,Debug,Clone,Copy)]
+pub struct Timestamp {
+    /// timetag of OSC bundle touch was sent in, None if not sent in a timed bundle
+    pub sensor: Option<f64>,
+    pub received: f64,
+}
+
+// (InputType, pressure, x, y, material, timestamp)
+pub type SenselMessage = (InputType, f32, f32, f32, u32, Timestamp);
+
+/// Inbound event for running slides
+pub enum Event {
+    /// OSC input, along with when it was received
+    Input(SenselMessage, Instant),
+    /// message from a client
+    Message(Message),
+}
 
 //-----------------------------------------------------------------------
 // Client to Server
@@ -53,20 +74,92 @@ pub struct Likert {
 }
 
 pub fn likert(data: Message) -> Result<Likert, ()> {
-    match data {
-        Value::Object(obj) => {
-            if obj.contains_key("type") {
-                let s : String = serde_json::from_value(obj["type"].clone()).unwrap();
-                if s == "likert" {
-                    return Ok(Likert {
-                        name: serde_json::from_value(obj["name"].clone()).unwrap(),
-                        value: serde_json::from_value(obj["value"].clone()).unwrap(),
-                    });
-                }
-            }
-        },
-        _ => {
+    if is_type("likert", data.clone()) {
+        return serde_json::from_value(data).map_err(|_| ());
+    }
+
+    return Err(());
+}
+
+/// returns the values of a submitted form
+pub fn form(data: Message) -> Result<serde_json::Map<String, Value>, ()> {
+    if is_type("form", data.clone()) {
+        match data.get("values") {
+            Some(Value::Object(values)) => return Ok(values.clone()),
+            _ => {},
+        }
+    }
+
+    return Err(());
+}
+
+/// returns the name of the subscale chosen in a NASA-TLX pairwise comparison
+pub fn tlxChoice(data: Message) -> Result<String, ()> {
+    if is_type("tlxChoice", data.clone()) {
+        match data.get("choice") {
+            Some(Value::String(choice)) => return Ok(choice.clone()),
+            _ => {},
+        }
+    }
+
+    return Err(());
+}
 
+/// returns the text of a free text response
+pub fn text(data: Message) -> Result<String, ()> {
+    if is_type("text", data.clone()) {
+        match data.get("value") {
+            Some(Value::String(text)) => return Ok(text.clone()),
+            _ => {},
+        }
+    }
+
+    return Err(());
+}
+
+/// Experimenter control of the running slide
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Control {
+    Skip,
+    Abort,
+    Repeat,
+    Pause,
+    Resume,
+}
+
+/// control message for action, as sent by an experimenter, only the terminal UI
+/// sends them from the backend
+#[cfg(feature = "tui")]
+pub fn controlAction(action: Control) -> Message {
+    let action = match action {
+        Control::Skip => "skip",
+        Control::Abort => "abort",
+        Control::Repeat => "repeat",
+        Control::Pause => "pause",
+        Control::Resume => "resume",
+    };
+
+    json!({
+        "type": "control",
+        "action": action
+    })
+}
+
+/// returns the action of an experimenter control message
+pub fn control(data: Message) -> Result<Control, ()> {
+    if is_type("control", data.clone()) {
+        match data.get("action") {
+            Some(Value::String(action)) => {
+                match action.as_str() {
+                    "skip" => return Ok(Control::Skip),
+                    "abort" => return Ok(Control::Abort),
+                    "repeat" => return Ok(Control::Repeat),
+                    "pause" => return Ok(Control::Pause),
+                    "resume" => return Ok(Control::Resume),
+                    _ => {},
+                }
+            },
+            _ => {},
         }
     }
 
@@ -98,10 +191,60 @@ pub fn is_consent(data: Message) -> bool {
     is_type("consent", data)
 }
 
+pub fn is_likert_confirm(data: Message) -> bool {
+    is_type("likertConfirm", data)
+}
+
+pub fn is_response_undo(data: Message) -> bool {
+    is_type("responseUndo", data)
+}
+
+pub fn is_response_confirm(data: Message) -> bool {
+    is_type("responseConfirm", data)
+}
+
+pub fn is_decline(data: Message) -> bool {
+    is_type("decline", data)
+}
+
 pub fn is_connected(data: Message) -> bool {
     is_type("connected", data)
 }
 
+/// returns the sequence number of a frame drawn by the client, along with the time 
+/// (ms) from the client receiving the frame to drawing it
+pub fn frameDrawn(data: Message) -> Result<(u64, f32), ()> {
+    if is_type("frameDrawn", data.clone()) {
+        match (data["seq"].as_u64(), data["drawMs"].as_f64()) {
+            (Some(seq), Some(draw_ms)) => return Ok((seq, draw_ms as f32)),
+            _ => {},
+        }
+    }
+
+    return Err(());
+}
+
+/// Role of a client, given when it connects
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Participant,
+    Experimenter,
+}
+
+/// returns the role of a connected message, clients that do not give a role are participants
+pub fn role(data: Message) -> Result<Role, ()> {
+    if is_connected(data.clone()) {
+        match data.get("role") {
+            None => return Ok(Role::Participant),
+            Some(Value::String(role)) if role == "participant" => return Ok(Role::Participant),
+            Some(Value::String(role)) if role == "experimenter" => return Ok(Role::Experimenter),
+            _ => {},
+        }
+    }
+
+    return Err(());
+}
+
 //-----------------------------------------------------------------------
 // Server to client
 //-----------------------------------------------------------------------
@@ -113,6 +256,49 @@ pub fn consentID(id: String) -> Message {
     })
 }
 
+pub fn likertDefinition(kind: &str, items: &Vec<LikertItem>, scale: u32, anchors: &Vec<String>) -> Message {
+    json!({
+        "type": "likertDefinition",
+        "kind": kind,
+        "items": items,
+        "scale": scale,
+        "anchors": anchors
+    })
+}
+
+pub fn likertInvalid(name: String, reason: String) -> Message {
+    json!({
+        "type": "likertInvalid",
+        "name": name,
+        "reason": reason
+    })
+}
+
+pub fn likertIncomplete(missing: Vec<String>) -> Message {
+    json!({
+        "type": "likertIncomplete",
+        "missing": missing
+    })
+}
+
+pub fn consentText(version: String, text: String) -> Message {
+    json!({
+        "type": "consentText",
+        "version": version,
+        "text": text
+    })
+}
+
+/// add sequence number and server timestamp (ms since UNIX epoch) to a render message, 
+/// so the client can acknowledge it has been drawn
+pub fn frame(mut data: Message, seq: u64, sent: u128) -> Message {
+    if let Value::Object(obj) = &mut data {
+        obj.insert("seq".to_string(), json!(seq));
+        obj.insert("sent".to_string(), json!(sent as u64));
+    }
+    data
+}
+
 pub fn press(circle: f32, ring: f32) -> Message {
     json!({
         "type": "press",
@@ -121,6 +307,16 @@ pub fn press(circle: f32, ring: f32) -> Message {
     })
 }
 
+pub fn hold(circle: f32, band_inner: f32, band_outer: f32, progress: f32) -> Message {
+    json!({
+        "type": "hold",
+        "circle": circle,
+        "band_inner": band_inner,
+        "band_outer": band_outer,
+        "progress": progress
+    })
+}
+
 pub fn slider(user_x: f32, box_x: f32, box_size: f32) -> Message {
     json!({
         "type": "slider",
@@ -138,6 +334,193 @@ pub fn tap(user_x: f32, arrow_x: f32) -> Message {
     })
 }
 
+pub fn target2D(cursor_x: f32, cursor_y: f32, target_x: f32, target_y: f32, target_size: f32) -> Message {
+    json!({
+        "type": "target2D",
+        "cursor_x": cursor_x,
+        "cursor_y": cursor_y,
+        "target_x": target_x,
+        "target_y": target_y,
+        "target_size": target_size
+    })
+}
+
+pub fn responseSelection(materials: &Vec<u32>, required: u32) -> Message {
+    json!({
+        "type": "responseSelection",
+        "materials": materials,
+        "required": required
+    })
+}
+
+pub fn responseRejected(material: u32, reason: String) -> Message {
+    json!({
+        "type": "responseRejected",
+        "material": material,
+        "reason": reason
+    })
+}
+
+pub fn pairwise(attribute: String, left: u32, right: u32, trial: u32, total: u32) -> Message {
+    json!({
+        "type": "pairwise",
+        "attribute": attribute,
+        "left": left,
+        "right": right,
+        "trial": trial,
+        "total": total
+    })
+}
+
+pub fn formSchema(title: String, fields: &Vec<FormField>) -> Message {
+    json!({
+        "type": "formSchema",
+        "title": title,
+        "fields": fields
+    })
+}
+
+pub fn formErrors(errors: serde_json::Map<String, Value>) -> Message {
+    json!({
+        "type": "formErrors",
+        "errors": errors
+    })
+}
+
+pub fn tlxPair(left: &LikertItem, right: &LikertItem, trial: u32, total: u32) -> Message {
+    json!({
+        "type": "tlxPair",
+        "left": left,
+        "right": right,
+        "trial": trial,
+        "total": total
+    })
+}
+
+pub fn textPrompt(prompt: String, min_length: usize, max_length: Option<usize>) -> Message {
+    json!({
+        "type": "textPrompt",
+        "prompt": prompt,
+        "min_length": min_length,
+        "max_length": max_length
+    })
+}
+
+pub fn textInvalid(reason: String) -> Message {
+    json!({
+        "type": "textInvalid",
+        "reason": reason
+    })
+}
+
+pub fn closing(debrief: String, summary: &Vec<(String, String)>) -> Message {
+    let summary: Vec<Value> = summary
+        .iter()
+        .map(|(label, value)| json!({ "label": label, "value": value }))
+        .collect();
+
+    json!({
+        "type": "closing",
+        "debrief": debrief,
+        "summary": summary
+    })
+}
+
+//-----------------------------------------------------------------------
+// Server to experimenter
+//-----------------------------------------------------------------------
+
+/// current slide of session, state is "running" or the slide's outcome once finished,
+/// paused is set while experimenter has the slide paused
+pub fn status(participant: String, index: usize, total: usize, slide: &str, state: &str) -> Message {
+    json!({
+        "type": "status",
+        "participant": participant,
+        "index": index,
+        "total": total,
+        "slide": slide,
+        "state": state,
+        "paused": false
+    })
+}
+
+/// number of participant and experimenter clients connected
+pub fn clients(participants: usize, experimenters: usize) -> Message {
+    json!({
+        "type": "clients",
+        "participants": participants,
+        "experimenters": experimenters
+    })
+}
+
+/// session has finished, e.g. last slide was run or it was aborted
+pub fn sessionEnded(participant: String) -> Message {
+    json!({
+        "type": "sessionEnded",
+        "participant": participant
+    })
+}
+
+/// results of trials so far
+pub fn trialStats(summary: &Vec<(String, String)>) -> Message {
+    let summary: Vec<Value> = summary
+        .iter()
+        .map(|(label, value)| json!({ "label": label, "value": value }))
+        .collect();
+
+    json!({
+        "type": "trialStats",
+        "summary": summary
+    })
+}
+
+/// results of current slide so far, sent while it runs
+pub fn liveStats(stats: &Vec<(String, String)>) -> Message {
+    let stats: Vec<Value> = stats
+        .iter()
+        .map(|(label, value)| json!({ "label": label, "value": value }))
+        .collect();
+
+    json!({
+        "type": "liveStats",
+        "stats": stats
+    })
+}
+
+/// messages per second received from each material, along with most recent input
+/// and number of OSC messages that matched no input so far
+pub fn inputActivity(rates: &Vec<(u32, f32)>, last: Option<&SenselMessage>, unmatched: u64) -> Message {
+    let rates: Vec<Value> = rates
+        .iter()
+        .map(|(material, rate)| json!({ "material": material, "rate": rate }))
+        .collect();
+
+    let last = last.map(|(input_type, pressure, x, y, material, _timestamp)| json!({
+        "input": format!("{:?}", input_type),
+        "pressure": pressure,
+        "x": x,
+        "y": y,
+        "material": material
+    }));
+
+    json!({
+        "type": "inputActivity",
+        "rates": rates,
+        "last": last,
+        "unmatched": unmatched
+    })
+}
+
+/// copy of a message sent to participant
+pub fn participant(data: Message) -> Message {
+    json!({
+        "type": "participant",
+        "message": data
+    })
+}
+
+//-----------------------------------------------------------------------
+
 pub fn materialIndex(index: u32, slide: u32) -> Message {
     json!({
         "type": "materialIndex",
@@ -200,6 +583,38 @@ pub fn gotoTap() -> Message {
     gotoSlide(tap_num())
 }
 
+pub fn gotoTarget2D() -> Message {
+    gotoSlide(target2d_num())
+}
+
+pub fn gotoHold() -> Message {
+    gotoSlide(hold_num())
+}
+
+pub fn gotoPairwise() -> Message {
+    gotoSlide(pairwise_num())
+}
+
+pub fn gotoForm() -> Message {
+    gotoSlide(form_num())
+}
+
+pub fn gotoTlxWeights() -> Message {
+    gotoSlide(tlx_weights_num())
+}
+
+pub fn gotoText() -> Message {
+    gotoSlide(text_num())
+}
+
+pub fn gotoDeclined() -> Message {
+    gotoSlide(declined_num())
+}
+
+pub fn gotoClosing() -> Message {
+    gotoSlide(closing_num())
+}
+
 pub fn front_matter_num() -> u32 {
     0
 }
@@ -240,3 +655,34 @@ pub fn tap_num() -> u32 {
     9
 }
 
+pub fn target2d_num() -> u32 {
+    10
+}
+
+pub fn hold_num() -> u32 {
+    11
+}
+
+pub fn pairwise_num() -> u32 {
+    12
+}
+
+pub fn form_num() -> u32 {
+    13
+}
+
+pub fn tlx_weights_num() -> u32 {
+    14
+}
+
+pub fn text_num() -> u32 {
+    15
+}
+
+pub fn declined_num() -> u32 {
+    16
+}
+
+pub fn closing_num() -> u32 {
+    17
+}
@@ -1,5 +1,6 @@
 extern crate rosc;
 
+use std::collections::HashMap;
 use std::net::{UdpSocket, SocketAddrV4};
 use std::str::FromStr;
 use rosc::{OscPacket, OscType};
@@ -7,13 +8,296 @@ use rosc::{OscPacket, OscType};
 use std::io::{stdin, stdout, Write};
 use std::error::Error;
 use std::sync::mpsc::{Sender};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use serde_json::Value;
 
 use crate::msg;
 
+//-----------------------------------------------------------------------------
+// Input mapping
+//-----------------------------------------------------------------------------
+
+/// Type of an OSC argument
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgType {
+    Int,
+    Long,
+    Float,
+    Double,
+}
+
+/// Position and type of the OSC argument a field is read from
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Arg {
+    pub arg: usize,
+    #[serde(rename = "type")]
+    pub arg_type: ArgType,
+}
+
+impl Arg {
+    fn new(arg: usize, arg_type: ArgType) -> Self {
+        Arg {
+            arg: arg,
+            arg_type: arg_type,
+        }
+    }
+
+    /// value of argument, if present and of expected type
+    fn value(&self, args: &Vec<OscType>) -> Result<f64, String> {
+        match (self.arg_type, args.get(self.arg)) {
+            (ArgType::Int, Some(OscType::Int(i))) => Ok(*i as f64),
+            (ArgType::Long, Some(OscType::Long(l))) => Ok(*l as f64),
+            (ArgType::Float, Some(OscType::Float(f))) => Ok(*f as f64),
+            (ArgType::Double, Some(OscType::Double(d))) => Ok(*d),
+            (_, Some(v)) => Err(format!("argument {} is {:?}, expected {:?}", self.arg, v, self.arg_type)),
+            (_, None) => Err(format!("argument {} is missing", self.arg)),
+        }
+    }
+}
+
+/// Mapping from OSC messages, with an address matching pattern, onto touches
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Mapping {
+    /// address pattern, * matches any characters and ? any single character, 
+    /// within a part of the address
+    pub address: String,
+    pub input_type: Arg,
+    pub pressure: Arg,
+    pub x: Arg,
+    pub y: Arg,
+    pub material: Arg,
+}
+
+impl Mapping {
+    /// /material, with arguments input type, pressure, x, y, and material index
+    pub fn material() -> Self {
+        Mapping {
+            address: "/material".to_string(),
+            input_type: Arg::new(0, ArgType::Int),
+            pressure: Arg::new(1, ArgType::Float),
+            x: Arg::new(2, ArgType::Float),
+            y: Arg::new(3, ArgType::Float),
+            material: Arg::new(4, ArgType::Int),
+        }
+    }
+
+    fn matches(&self, address: &str) -> bool {
+        matches(self.address.as_bytes(), address.as_bytes())
+    }
+
+    /// touch from message arguments, input type must be 0 (start), 1 (move) or 2 (end)
+    fn touch(&self, args: &Vec<OscType>, timestamp: msg::Timestamp) -> Result<msg::SenselMessage, String> {
+        let input_type = self.input_type.value(args)?;
+        if input_type.fract() != 0.0 || input_type < 0.0 || input_type > 2.0 {
+            return Err(format!("input type {} is not 0, 1 or 2", input_type));
+        }
+
+        Ok((msg::InputType::new(input_type as u32),
+            self.pressure.value(args)? as f32,
+            self.x.value(args)? as f32,
+            self.y.value(args)? as f32,
+            self.material.value(args)? as u32,
+            timestamp))
+    }
+}
+
+/// does OSC address match pattern
+fn matches(pattern: &[u8], address: &[u8]) -> bool {
+    match (pattern.first(), address.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            matches(&pattern[1..], address) ||
+            (address.first().map_or(false, |c| *c != b'/') && matches(pattern, &address[1..]))
+        },
+        (Some(b'?'), Some(c)) if *c != b'/' => matches(&pattern[1..], &address[1..]),
+        (Some(p), Some(c)) if p == c => matches(&pattern[1..], &address[1..]),
+        _ => false,
+    }
+}
+
+//-----------------------------------------------------------------------------
+// TUIO
+//-----------------------------------------------------------------------------
+
+/// Region of a TUIO surface, in normalised (0 to 1) surface coordinates, that is
+/// a material
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Region {
+    pub material: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Region {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// TUIO surface, divided into regions that are materials
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TuioConfig {
+    pub regions: Vec<Region>,
+    /// x and y, within a region, are scaled onto 0 to size
+    #[serde(default = "TuioConfig::default_size")]
+    pub size: (f32, f32),
+    /// pressure of TUIO 1.1 cursors, which have none, and onto which TUIO 2.0 
+    /// pointer pressure (0 to 1) is scaled
+    #[serde(default = "TuioConfig::default_pressure")]
+    pub pressure: f32,
+}
+
+impl TuioConfig {
+    fn default_size() -> (f32, f32) {
+        (100.0, 100.0)
+    }
+
+    fn default_pressure() -> f32 {
+        1000.0
+    }
+
+    /// check regions are consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.regions.is_empty() {
+            return Err("tuio needs at least one region".to_string());
+        }
+        if self.regions.iter().any(|r| !(r.width > 0.0 && r.height > 0.0)) {
+            return Err("tuio region width and height must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Cursor session, along with region it started in, None if it started outside 
+/// all regions
+#[derive(Clone, Copy)]
+struct Cursor {
+    region: Option<Region>,
+    x: f32,
+    y: f32,
+    pressure: f32,
+}
+
+/// Tracks TUIO cursor sessions, turning them into touches. A touch is on the 
+/// material of the region its session started in, moving out of the region does 
+/// not change material.
+struct Tuio {
+    config: TuioConfig,
+    sessions: HashMap<i32, Cursor>,
+}
+
+impl Tuio {
+    const CURSOR: &'static str = "/tuio/2Dcur";
+    const POINTER: &'static str = "/tuio2/ptr";
+    const ALIVE: &'static str = "/tuio2/alv";
+    const FRAME: &'static str = "/tuio2/frm";
+
+    fn new(config: TuioConfig) -> Self {
+        Tuio {
+            config: config,
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// is address a TUIO 1.1 or 2.0 address
+    fn handles(addr: &str) -> bool {
+        addr.starts_with("/tuio/") || addr.starts_with("/tuio2/")
+    }
+
+    /// touches started, moved, or ended by message. Err if message is not a cursor 
+    /// (1.1) or pointer (2.0) message, as expected
+    fn touches(&mut self, addr: &str, args: &Vec<OscType>, timestamp: msg::Timestamp) -> Result<Vec<msg::SenselMessage>, String> {
+        let int = |arg| Arg::new(arg, ArgType::Int).value(args).map(|v| v as i32);
+        let float = |arg| Arg::new(arg, ArgType::Float).value(args).map(|v| v as f32);
+
+        if addr == Tuio::CURSOR {
+            // /tuio/2Dcur set s x y X Y m, /tuio/2Dcur alive s..., /tuio/2Dcur fseq f
+            match args.get(0) {
+                Some(OscType::String(command)) if command == "set" => {
+                    Ok(self.set(int(1)?, float(2)?, float(3)?, self.config.pressure, timestamp))
+                },
+                Some(OscType::String(command)) if command == "alive" => {
+                    let ids = (1..args.len()).map(int).collect::<Result<Vec<i32>, String>>()?;
+                    Ok(self.alive(ids, timestamp))
+                },
+                Some(OscType::String(command)) if command == "fseq" || command == "source" => Ok(vec![]),
+                command => Err(format!("unexpected TUIO cursor command {:?}", command)),
+            }
+        }
+        else if addr == Tuio::POINTER {
+            // /tuio2/ptr s_id tu_id c_id x_pos y_pos angle shear radius press ...
+            let pressure = float(8)? * self.config.pressure;
+            Ok(self.set(int(0)?, float(3)?, float(4)?, pressure, timestamp))
+        }
+        else if addr == Tuio::ALIVE {
+            let ids = (0..args.len()).map(int).collect::<Result<Vec<i32>, String>>()?;
+            Ok(self.alive(ids, timestamp))
+        }
+        else if addr == Tuio::FRAME {
+            Ok(vec![])
+        }
+        else {
+            Err("unsupported TUIO profile".to_string())
+        }
+    }
+
+    /// cursor set, starting session if new
+    fn set(&mut self, session: i32, x: f32, y: f32, pressure: f32, timestamp: msg::Timestamp) -> Vec<msg::SenselMessage> {
+        let input_type = if self.sessions.contains_key(&session) { msg::InputType::Move } else { msg::InputType::Start };
+        let region = match self.sessions.get(&session) {
+            Some(cursor) => cursor.region,
+            None => self.config.regions.iter().find(|r| r.contains(x, y)).cloned(),
+        };
+
+        let cursor = Cursor { region: region, x: x, y: y, pressure: pressure };
+        self.sessions.insert(session, cursor);
+        self.touch(input_type, &cursor, timestamp).into_iter().collect()
+    }
+
+    /// sessions still alive, any others have ended
+    fn alive(&mut self, ids: Vec<i32>, timestamp: msg::Timestamp) -> Vec<msg::SenselMessage> {
+        let mut ended: Vec<i32> = self.sessions.keys().filter(|s| !ids.contains(s)).cloned().collect();
+        ended.sort();
+
+        let mut touches = vec![];
+        for session in ended {
+            if let Some(cursor) = self.sessions.remove(&session) {
+                touches.extend(self.touch(msg::InputType::End, &cursor, timestamp));
+            }
+        }
+        touches
+    }
+
+    /// touch for cursor, with position relative to its region. None if cursor is 
+    /// not in a region
+    fn touch(&self, input_type: msg::InputType, cursor: &Cursor, timestamp: msg::Timestamp) -> Option<msg::SenselMessage> {
+        cursor.region.map(|region| (
+            input_type,
+            cursor.pressure,
+            (cursor.x - region.x) / region.width * self.config.size.0,
+            (cursor.y - region.y) / region.height * self.config.size.1,
+            region.material,
+            timestamp))
+    }
+}
+
+//-----------------------------------------------------------------------------
+// OSC input
+//-----------------------------------------------------------------------------
+
 pub struct OSC {
-    sender: Sender<msg::SenselMessage>,
+    sender: Sender<(msg::SenselMessage, Instant)>,
     socket: UdpSocket,
+    mappings: Vec<Mapping>,
+    tuio: Option<Tuio>,
+    /// number of messages that failed to match any mapping, shared so it can be
+    /// reported to experimenters
+    unmatched: Arc<AtomicU64>,
 }
 
 unsafe impl Send for OSC {
@@ -22,15 +306,24 @@ unsafe impl Send for OSC {
 
 impl OSC {
 
-    const MATERIAL_PREFIX: &'static str = "/material";
+    /// seconds between NTP (1900) and Unix (1970) epochs
+    const NTP_UNIX_OFFSET: f64 = 2208988800.0;
     // const SYNTH_PREFIX: &'static str = "/synth/";
     // const EFFECT_PREFIX: &'static str = "/effect/";
     // const MASTER_PREFIX: &'static str = "/master/";
 
-    pub fn new(sender:Sender<msg::SenselMessage>, socket: UdpSocket) -> Self {
+    pub fn new(
+        sender:Sender<(msg::SenselMessage, Instant)>, 
+        socket: UdpSocket, 
+        mappings: Vec<Mapping>, 
+        tuio: Option<TuioConfig>,
+        unmatched: Arc<AtomicU64>) -> Self {
         OSC {
             sender: sender,
             socket: socket,
+            mappings: mappings,
+            tuio: tuio.map(Tuio::new),
+            unmatched: unmatched,
         }
     }
 
@@ -38,76 +331,81 @@ impl OSC {
         
     }
 
-    fn match_int(v: OscType) -> i32 {
-        match v {
-            OscType::Int(i) => i,
-            _ => {
-                error!("expected OscType::Int");
-                0
-            }
-        }
-    }
-
-    fn toFloat(osct: &OscType) -> f32 {
-        match *osct {
-            OscType::Float(f) => {
-                f
+    /// OSC timetag, NTP seconds and fraction of second since 1900, as ms since 
+    /// the Unix epoch. None for immediate, i.e. not timed
+    fn timetag(timetag: &OscType) -> Option<f64> {
+        match *timetag {
+            OscType::Time(0, 1) => None,
+            OscType::Time(seconds, fraction) => {
+                Some((seconds as f64 - OSC::NTP_UNIX_OFFSET) * 1000.0 + fraction as f64 * 1000.0 / 4294967296.0)
             },
-            _ => {
-                0.0
-            }
+            _ => None,
         }
     }
 
-    fn toInt(osct: &OscType) -> u32 {
-        match *osct {
-            OscType::Int(i) => {
-                i as u32
-            },
-            _ => {
-                0
+    /// handle packet, unpacking bundles recursively. Messages are timestamped by 
+    /// the timetag of innermost timed bundle they are in
+    fn handle_packet(&mut self, packet: OscPacket, timetag: Option<f64>, received: (Instant, f64)) {
+        match packet {
+            OscPacket::Message(msg) => { 
+                let addr = msg.addr;
+                let args = msg.args.unwrap_or(vec![]);
+                let timestamp = msg::Timestamp { sensor: timetag, received: received.1 };
+
+                // TUIO surface, if configured, otherwise first mapping whose address matches, 
+                // and whose arguments are as expected
+                let mut reason = "no mapping for address".to_string();
+                if let Some(tuio) = self.tuio.as_mut().filter(|_| Tuio::handles(&addr)) {
+                    match tuio.touches(&addr, &args, timestamp) {
+                        Ok(touches) => {
+                            for touch in touches {
+                                self.sender.send((touch, received.0)).ok();
+                            }
+                            return;
+                        },
+                        Err(e) => reason = e,
+                    }
+                }
+
+                for mapping in self.mappings.iter().filter(|m| m.matches(&addr)) {
+                    match mapping.touch(&args, timestamp) {
+                        Ok(touch) => {
+                            info!("received touch from {}", addr);
+                            self.sender.send((touch, received.0)).ok();
+                            return;
+                        },
+                        Err(e) => reason = e,
+                    }
+                }
+
+                let unmatched = self.unmatched.fetch_add(1, Ordering::Relaxed) + 1;
+                error!("Unmatched osc message {} {:?}, {} ({} unmatched)", addr, args, reason, unmatched);
+            }
+            OscPacket::Bundle(bundle) => {
+                let timetag = OSC::timetag(&bundle.timetag).or(timetag);
+                for packet in bundle.content {
+                    self.handle_packet(packet, timetag, received);
+                }
             }
         }
     }
 
-
     pub fn run(mut osc: OSC) {
-        let sender = osc.sender.clone();
-  
         let mut buf = [0u8; rosc::decoder::MTU];
 
         loop {
             match osc.socket.recv_from(&mut buf) {
                 Ok((size, addr)) => {
+                    // time input arrived, for measuring latency to display, and as 
+                    // recorded with input
+                    let received = Instant::now();
+                    let received_ms = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0.0, |d| d.as_secs_f64() * 1000.0);
                     info!("Received osc packet with size {} from: {}", size, addr);
-                    let packet = rosc::decoder::decode(&buf[..size]).unwrap();
-                    //handle_packet(packet);
-                     match packet {
-                        OscPacket::Message(msg) => { 
-                            if msg.addr == OSC::MATERIAL_PREFIX {
-                                    info!("received material message");
-                                    // expecting two arguments (pressure and material index)
-                                    match msg.args {
-                                        Some(vargs) => {
-                                            if vargs.len() == 5{
-                                                osc.sender.send(
-                                                    (msg::InputType::new(OSC::toInt(&vargs[0])), 
-                                                     OSC::toFloat(&vargs[1]),
-                                                     OSC::toFloat(&vargs[2]),
-                                                     OSC::toFloat(&vargs[3]),
-                                                     OSC::toInt(&vargs[4])));
-                                            }
-                                        },
-                                        _ => {
-                                            // ignore invalid message
-                                        }
-
-                                }
-                            }
-                        }
-                        OscPacket::Bundle(bundle) => {
-                            info!("OSC Bundle: {:?}", bundle);
-                        }
+                    match rosc::decoder::decode(&buf[..size]) {
+                        Ok(packet) => osc.handle_packet(packet, None, (received, received_ms)),
+                        Err(e) => error!("Invalid osc packet: {:?}", e),
                     }
                 }
                 Err(e) => {
@@ -129,7 +427,11 @@ impl OSCBuilder {
     const IPADDRESS: &'static str = "127.0.0.1";
     const UDP_PORT: &'static str = "8338";
 
-    pub fn new(sender: Sender<msg::SenselMessage>) -> Self {
+    pub fn new(
+        sender: Sender<(msg::SenselMessage, Instant)>, 
+        mappings: Vec<Mapping>, 
+        tuio: Option<TuioConfig>,
+        unmatched: Arc<AtomicU64>) -> Self {
 
         let address = format!("{}:{}", OSCBuilder::IPADDRESS, OSCBuilder::UDP_PORT);
         let addr = match SocketAddrV4::from_str(&address) {
@@ -142,7 +444,7 @@ impl OSCBuilder {
 
 
         Self {
-            osc: OSC::new(sender,sock),
+            osc: OSC::new(sender, sock, mappings, tuio, unmatched),
             initialized: false,
         }
     }
@@ -160,4 +462,138 @@ impl OSCBuilder {
         }
         return self.osc;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp() -> msg::Timestamp {
+        msg::Timestamp { sensor: None, received: 0.0 }
+    }
+
+    #[test]
+    fn matches_address_patterns() {
+        assert!(matches(b"/material", b"/material"));
+        assert!(!matches(b"/material", b"/materials"));
+        assert!(matches(b"/bridge/*/touch", b"/bridge/left/touch"));
+        assert!(matches(b"/bridge/*/touch", b"/bridge//touch"));
+        assert!(!matches(b"/bridge/*/touch", b"/bridge/a/b/touch"));
+        assert!(matches(b"/pad?", b"/pad1"));
+        assert!(!matches(b"/pad?", b"/pad"));
+        assert!(!matches(b"/pad?x", b"/pad/x"));
+        assert!(matches(b"/*", b"/anything"));
+    }
+
+    #[test]
+    fn mapping_touch() {
+        let mapping = Mapping::material();
+        let args = vec![OscType::Int(1), OscType::Float(500.0), OscType::Float(10.0), OscType::Float(20.0), OscType::Int(3)];
+        let (input_type, pressure, x, y, material, _) = mapping.touch(&args, timestamp()).unwrap();
+        assert_eq!(input_type, msg::InputType::Move);
+        assert_eq!((pressure, x, y, material), (500.0, 10.0, 20.0, 3));
+
+        // arguments of wrong type or missing
+        let args = vec![OscType::Int(1), OscType::Int(500), OscType::Float(10.0), OscType::Float(20.0), OscType::Int(3)];
+        assert!(mapping.touch(&args, timestamp()).is_err());
+        assert!(mapping.touch(&vec![OscType::Int(1)], timestamp()).is_err());
+    }
+
+    #[test]
+    fn mapping_touch_input_type() {
+        let mut mapping = Mapping::material();
+        mapping.input_type = Arg::new(0, ArgType::Float);
+        let args = |input_type: f32| vec![OscType::Float(input_type), OscType::Float(500.0), OscType::Float(10.0), OscType::Float(20.0), OscType::Int(3)];
+
+        assert_eq!(mapping.touch(&args(0.0), timestamp()).unwrap().0, msg::InputType::Start);
+        assert_eq!(mapping.touch(&args(2.0), timestamp()).unwrap().0, msg::InputType::End);
+        assert!(mapping.touch(&args(1.7), timestamp()).is_err());
+        assert!(mapping.touch(&args(-1.0), timestamp()).is_err());
+        assert!(mapping.touch(&args(3.0), timestamp()).is_err());
+    }
+
+    fn tuio() -> Tuio {
+        Tuio::new(TuioConfig {
+            regions: vec![
+                Region { material: 1, x: 0.0, y: 0.0, width: 0.5, height: 1.0 },
+                Region { material: 2, x: 0.5, y: 0.0, width: 0.5, height: 1.0 },
+            ],
+            size: (100.0, 100.0),
+            pressure: 1000.0,
+        })
+    }
+
+    fn cursor(command: &str, args: Vec<OscType>) -> Vec<OscType> {
+        let mut cursor = vec![OscType::String(command.to_string())];
+        cursor.extend(args);
+        cursor
+    }
+
+    fn set(session: i32, x: f32, y: f32) -> Vec<OscType> {
+        cursor("set", vec![OscType::Int(session), OscType::Float(x), OscType::Float(y),
+            OscType::Float(0.0), OscType::Float(0.0), OscType::Float(0.0)])
+    }
+
+    #[test]
+    fn tuio_cursor_session() {
+        let mut tuio = tuio();
+        let touches = tuio.touches(Tuio::CURSOR, &set(7, 0.25, 0.5), timestamp()).unwrap();
+        assert_eq!(touches.len(), 1);
+        let (input_type, pressure, x, y, material, _) = touches[0];
+        assert_eq!(input_type, msg::InputType::Start);
+        assert_eq!((pressure, x, y, material), (1000.0, 50.0, 50.0, 1));
+
+        // moving into another region keeps material session started on
+        let touches = tuio.touches(Tuio::CURSOR, &set(7, 0.75, 0.5), timestamp()).unwrap();
+        assert_eq!(touches[0].0, msg::InputType::Move);
+        assert_eq!(touches[0].4, 1);
+
+        // session no longer alive has ended, at its last position
+        let touches = tuio.touches(Tuio::CURSOR, &cursor("alive", vec![]), timestamp()).unwrap();
+        assert_eq!(touches.len(), 1);
+        assert_eq!(touches[0].0, msg::InputType::End);
+        assert_eq!(touches[0].2, 150.0);
+        assert!(tuio.sessions.is_empty());
+
+        assert!(tuio.touches(Tuio::CURSOR, &cursor("fseq", vec![OscType::Int(1)]), timestamp()).unwrap().is_empty());
+        assert!(tuio.touches(Tuio::CURSOR, &cursor("other", vec![]), timestamp()).is_err());
+    }
+
+    #[test]
+    fn tuio_alive_ends_only_missing_sessions() {
+        let mut tuio = tuio();
+        tuio.touches(Tuio::CURSOR, &set(1, 0.1, 0.1), timestamp()).unwrap();
+        tuio.touches(Tuio::CURSOR, &set(2, 0.6, 0.1), timestamp()).unwrap();
+        tuio.touches(Tuio::CURSOR, &set(3, 0.7, 0.1), timestamp()).unwrap();
+
+        let touches = tuio.touches(Tuio::CURSOR, &cursor("alive", vec![OscType::Int(2)]), timestamp()).unwrap();
+        assert_eq!(touches.iter().map(|t| (t.0, t.4)).collect::<Vec<_>>(),
+            vec![(msg::InputType::End, 1), (msg::InputType::End, 2)]);
+        assert_eq!(tuio.sessions.keys().cloned().collect::<Vec<i32>>(), vec![2]);
+    }
+
+    #[test]
+    fn tuio_outside_regions() {
+        let mut tuio = tuio();
+        // tracked, but no touches, as it is not on a material
+        assert!(tuio.touches(Tuio::CURSOR, &set(1, 0.5, 1.5), timestamp()).unwrap().is_empty());
+        assert!(tuio.touches(Tuio::CURSOR, &set(1, 0.25, 0.5), timestamp()).unwrap().is_empty());
+        assert!(tuio.touches(Tuio::CURSOR, &cursor("alive", vec![]), timestamp()).unwrap().is_empty());
+        assert!(tuio.sessions.is_empty());
+    }
+
+    #[test]
+    fn tuio2_pointer() {
+        let mut tuio = tuio();
+        let pointer = vec![OscType::Int(4), OscType::Int(0), OscType::Int(0), OscType::Float(0.5), OscType::Float(0.25),
+            OscType::Float(0.0), OscType::Float(0.0), OscType::Float(0.0), OscType::Float(0.5)];
+        let touches = tuio.touches(Tuio::POINTER, &pointer, timestamp()).unwrap();
+        let (input_type, pressure, x, y, material, _) = touches[0];
+        assert_eq!(input_type, msg::InputType::Start);
+        assert_eq!((pressure, x, y, material), (500.0, 0.0, 25.0, 2));
+
+        let touches = tuio.touches(Tuio::ALIVE, &vec![], timestamp()).unwrap();
+        assert_eq!(touches[0].0, msg::InputType::End);
+        assert!(tuio.touches(Tuio::FRAME, &vec![OscType::Int(1)], timestamp()).unwrap().is_empty());
+        assert!(tuio.touches("/tuio/2Dobj", &vec![], timestamp()).is_err());
+    }
+}
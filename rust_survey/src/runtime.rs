@@ -0,0 +1,686 @@
+//! Slide runtime
+//!
+//! Copyright: Benedict R. Gaster
+//!
+//! OSC input and client messages arrive on a single channel of events, which a
+//! running slide blocks on, along with a frame tick for slides that animate.
+//! Experimenter control messages are handled here, so slides only see input
+//! and participant messages. Time a slide is paused for is added to its
+//! deadline, and handlers are told so their timers can skip it.
+//!
+//! Frames are ticked at a fixed rate, on a schedule from when the slide started
+//! rather than from the previous tick, so they do not drift. Each frame rendered
+//! is numbered and timestamped, and the client acknowledges once it is drawn, so
+//! that latency from input to display can be estimated.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::msg;
+use crate::msg::Event;
+use crate::slide::Outcome;
+use crate::ws_server;
+
+//-----------------------------------------------------------------------------
+// Events
+//-----------------------------------------------------------------------------
+
+/// Inbound events for slides
+pub struct Events {
+    receiver: Receiver<Event>,
+    /// events kept when input is flushed, handled before any new events
+    pending: RefCell<VecDeque<Event>>,
+    /// time between frames of animated slides
+    frame: Duration,
+    /// time current slide has been paused for by experimenter
+    paused: Cell<Duration>,
+    /// experimenters are told when slide is paused and resumed
+    monitor: ws_server::Monitor,
+}
+
+impl Events {
+    /// frame rate (Hz) is for animated slides
+    pub fn new(receiver: Receiver<Event>, frame_rate: f32, monitor: ws_server::Monitor) -> Self {
+        Events {
+            receiver: receiver,
+            pending: RefCell::new(VecDeque::new()),
+            frame: Duration::from_secs_f32(1.0 / frame_rate),
+            paused: Cell::new(Duration::from_secs(0)),
+            monitor: monitor,
+        }
+    }
+
+    /// start of a slide, from when pauses are accounted
+    pub fn start_slide(&self) {
+        self.paused.set(Duration::from_secs(0));
+    }
+
+    /// slide deadline, extended by time slide has been paused for
+    fn extend(&self, deadline: Option<Instant>) -> Option<Instant> {
+        deadline.map(|deadline| deadline + self.paused.get())
+    }
+
+    /// clock for an animated slide, ticking at configured frame rate
+    pub fn clock(&self) -> Clock {
+        Clock::new(self.frame)
+    }
+
+    /// block for next event, until time (if any) passes
+    pub fn recv(&self, until: Option<Instant>) -> Result<Event, RecvTimeoutError> {
+        if let Some(event) = self.pending.borrow_mut().pop_front() {
+            return Ok(event);
+        }
+
+        match until {
+            Some(until) => {
+                let now = Instant::now();
+                if now >= until {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+                self.receiver.recv_timeout(until - now)
+            },
+            None => self.receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        }
+    }
+
+    /// discard any input received so far, e.g. after touches from a previous slide
+    pub fn flush_input(&self) {
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                Event::Input(..) => {},
+                event => self.pending.borrow_mut().push_back(event),
+            }
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Control
+//-----------------------------------------------------------------------------
+
+/// Result of an experimenter control
+enum Control {
+    /// slide carries on, after being paused for duration
+    Resumed(Duration),
+    Finished(Outcome),
+}
+
+/// handle an experimenter control. Pausing blocks until slide is resumed or 
+/// interrupted, input while paused is dropped, but participant messages are kept
+/// to be handled once resumed. Time paused is added to the slide's deadline.
+fn handle_control(control: msg::Control, events: &Events) -> Control {
+    match control {
+        msg::Control::Skip => Control::Finished(Outcome::Skipped),
+        msg::Control::Abort => Control::Finished(Outcome::Aborted),
+        msg::Control::Repeat => Control::Finished(Outcome::Repeated),
+        msg::Control::Resume => Control::Resumed(Duration::from_secs(0)),
+        msg::Control::Pause => {
+            info!("slide paused");
+            events.monitor.paused(true);
+            let start = Instant::now();
+            let mut kept = vec![];
+            let control = loop {
+                match events.recv(None) {
+                    Ok(Event::Message(data)) => {
+                        match msg::control(data.clone()) {
+                            Ok(msg::Control::Pause) => {},
+                            Ok(control) => break Some(control),
+                            Err(_) => kept.push(Event::Message(data)),
+                        }
+                    },
+                    Ok(Event::Input(..)) => {},
+                    Err(_) => break None,
+                }
+            };
+            events.pending.borrow_mut().extend(kept);
+
+            let paused = start.elapsed();
+            events.paused.set(events.paused.get() + paused);
+            events.monitor.paused(false);
+            match control {
+                Some(msg::Control::Resume) => {
+                    info!("slide resumed");
+                    Control::Resumed(paused)
+                },
+                Some(control) => handle_control(control, events),
+                None => Control::Finished(Outcome::Aborted),
+            }
+        },
+    }
+}
+
+/// Result of waiting for a client message
+pub enum Wait {
+    Message(msg::Message),
+    Interrupted(Outcome),
+}
+
+/// block for next client message, experimenter control and passing the deadline
+/// interrupt the wait. Input, and late acknowledgements of frames, are dropped.
+pub fn wait_msg(events: &Events, deadline: Option<Instant>) -> Wait {
+    loop {
+        match events.recv(events.extend(deadline)) {
+            Ok(Event::Message(data)) => {
+                match msg::control(data.clone()) {
+                    Ok(control) => {
+                        if let Control::Finished(outcome) = handle_control(control, events) {
+                            return Wait::Interrupted(outcome);
+                        }
+                    },
+                    Err(_) if msg::frameDrawn(data.clone()).is_ok() => {},
+                    Err(_) => return Wait::Message(data),
+                }
+            },
+            Ok(Event::Input(..)) => {},
+            Err(RecvTimeoutError::Timeout) => return Wait::Interrupted(Outcome::TimedOut),
+            Err(RecvTimeoutError::Disconnected) => return Wait::Interrupted(Outcome::Aborted),
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Handlers
+//-----------------------------------------------------------------------------
+
+/// A running slide, each handler returns an outcome once the slide has finished
+pub trait Handler {
+    /// OSC input
+    fn input(&mut self, _input: msg::SenselMessage, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        None
+    }
+
+    /// message from participant
+    fn message(&mut self, _data: msg::Message, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        None
+    }
+
+    /// frame tick, dt is time (seconds) since previous tick
+    fn tick(&mut self, _dt: f32, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        None
+    }
+
+    /// view to be drawn for this frame, if it has changed, called after each tick
+    fn render(&mut self) -> Option<msg::Message> {
+        None
+    }
+
+    /// slide was paused for duration, timers should not count it
+    fn paused(&mut self, _duration: Duration) {
+    }
+}
+
+/// run handler until it finishes, deadline passes or it is interrupted by the
+/// experimenter. If a clock is given handler is ticked by it, and once finished
+/// frames still in flight are given a moment to be acknowledged.
+pub fn run(
+    handler: &mut dyn Handler,
+    events: &Events,
+    outbound_msg: &ws_server::WSServer,
+    deadline: Option<Instant>,
+    mut clock: Option<&mut Clock>) -> Outcome {
+
+    let outcome = run_handler(handler, events, outbound_msg, deadline, clock.as_deref_mut());
+    if let Some(clock) = clock {
+        clock.latency.settle(events);
+    }
+    outcome
+}
+
+fn run_handler(
+    handler: &mut dyn Handler,
+    events: &Events,
+    outbound_msg: &ws_server::WSServer,
+    deadline: Option<Instant>,
+    mut clock: Option<&mut Clock>) -> Outcome {
+
+    if let Some(clock) = clock.as_mut() {
+        clock.start();
+    }
+
+    loop {
+        let next_tick = clock.as_ref().map(|clock| clock.next);
+        let until = match (next_tick, events.extend(deadline)) {
+            (Some(tick), Some(deadline)) => Some(Instant::min(tick, deadline)),
+            (tick, deadline) => tick.or(deadline),
+        };
+
+        let outcome = match events.recv(until) {
+            Ok(Event::Input(input, received)) => {
+                if let Some(clock) = clock.as_mut() {
+                    clock.latency.input(received);
+                }
+                handler.input(input, outbound_msg)
+            },
+            Ok(Event::Message(data)) => {
+                match (msg::control(data.clone()), msg::frameDrawn(data.clone())) {
+                    (Ok(control), _) => {
+                        match handle_control(control, events) {
+                            Control::Resumed(paused) => {
+                                if let Some(clock) = clock.as_mut() {
+                                    clock.paused(paused);
+                                }
+                                handler.paused(paused);
+                                None
+                            },
+                            Control::Finished(outcome) => Some(outcome),
+                        }
+                    },
+                    (_, Ok((seq, draw_ms))) => {
+                        if let Some(clock) = clock.as_mut() {
+                            clock.latency.drawn(seq, draw_ms);
+                        }
+                        None
+                    },
+                    _ => handler.message(data, outbound_msg),
+                }
+            },
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Outcome::Aborted),
+        };
+        if let Some(outcome) = outcome {
+            return outcome;
+        }
+
+        let now = Instant::now();
+        if let Some(deadline) = events.extend(deadline) {
+            if now >= deadline {
+                return Outcome::TimedOut;
+            }
+        }
+
+        if let Some(clock) = clock.as_mut() {
+            if now >= clock.next {
+                let dt = clock.tick(now);
+                if let Some(outcome) = handler.tick(dt, outbound_msg) {
+                    return outcome;
+                }
+                if let Some(data) = handler.render() {
+                    outbound_msg.send(clock.latency.frame(data));
+                }
+            }
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Live stats
+//-----------------------------------------------------------------------------
+
+/// Results of a running slide, sent to experimenters at most once a second
+pub struct LiveStats {
+    sent: Option<Instant>,
+}
+
+impl LiveStats {
+    const INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        LiveStats {
+            sent: None,
+        }
+    }
+
+    /// send (label, value) stats, if they are due
+    pub fn update<F: FnOnce() -> Vec<(String, String)>>(&mut self, outbound_msg: &ws_server::WSServer, stats: F) {
+        let now = Instant::now();
+        if self.sent.map_or(true, |sent| now - sent >= LiveStats::INTERVAL) {
+            self.sent = Some(now);
+            outbound_msg.send_experimenter(msg::liveStats(&stats()));
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Frame clock
+//-----------------------------------------------------------------------------
+
+/// Fixed rate frame clock, along with statistics of the intervals between frames
+pub struct Clock {
+    /// nominal time between frames
+    interval: Duration,
+    /// when next frame is due
+    next: Instant,
+    last: Instant,
+    /// number of frames ticked
+    frames: u32,
+    /// sum of intervals between frames, and their squares (seconds)
+    sum: f64,
+    sum_sq: f64,
+    /// frames that were due but not ticked, as an earlier frame ran late
+    missed: u32,
+    latency: Latency,
+}
+
+impl Clock {
+    pub fn new(interval: Duration) -> Self {
+        let now = Instant::now();
+        Clock {
+            interval: interval,
+            next: now + interval,
+            last: now,
+            frames: 0,
+            sum: 0.0,
+            sum_sq: 0.0,
+            missed: 0,
+            latency: Latency::new(),
+        }
+    }
+
+    /// first frame is due one interval from now
+    fn start(&mut self) {
+        let now = Instant::now();
+        self.last = now;
+        self.next = now + self.interval;
+    }
+
+    /// slide was paused for duration, so frames were not due
+    fn paused(&mut self, duration: Duration) {
+        self.next = self.next + duration;
+        self.last = self.last + duration;
+    }
+
+    /// tick a frame, returning time (seconds) since previous frame. Any frames
+    /// due since are skipped, so the schedule is kept.
+    fn tick(&mut self, now: Instant) -> f32 {
+        let late = (now - self.next).as_secs_f64();
+        let skipped = (late / self.interval.as_secs_f64()) as u32;
+        self.missed = self.missed + skipped;
+        self.next = self.next + self.interval * (skipped + 1);
+
+        let dt = (now - self.last).as_secs_f64();
+        self.last = now;
+        self.frames = self.frames + 1;
+        self.sum = self.sum + dt;
+        self.sum_sq = self.sum_sq + dt * dt;
+        dt as f32
+    }
+
+    /// number of frames ticked
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// nominal interval (ms) between frames
+    pub fn interval_ms(&self) -> f32 {
+        self.interval.as_secs_f32() * 1000.0
+    }
+
+    /// mean interval (ms) between frames
+    pub fn mean_ms(&self) -> f32 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        (self.sum / self.frames as f64 * 1000.0) as f32
+    }
+
+    /// jitter, standard deviation of interval (ms) between frames
+    pub fn jitter_ms(&self) -> f32 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        let n = self.frames as f64;
+        let mean = self.sum / n;
+        (f64::max(self.sum_sq / n - mean * mean, 0.0).sqrt() * 1000.0) as f32
+    }
+
+    /// number of frames missed
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+
+    /// latency of frames rendered
+    pub fn latency(&self) -> &Latency {
+        &self.latency
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Latency
+//-----------------------------------------------------------------------------
+
+/// Latency of rendered frames. Round trip is from a frame being sent to its 
+/// acknowledgement, after the client has drawn it. The frame is assumed to reach
+/// the client in half the round trip, excluding the time the client took to draw
+/// it, so input to display is estimated as the time from the oldest input not yet
+/// rendered to sending the frame, plus half the network round trip and the time
+/// to draw.
+///
+/// Frames are numbered across all slides, so a late acknowledgement of a previous
+/// slide's frame is not taken for one of the current slide.
+pub struct Latency {
+    /// number of frames sent
+    sent: u64,
+    /// oldest input received since last frame was sent
+    pending_input: Option<Instant>,
+    /// frames sent but not yet drawn, time sent and oldest input rendered 
+    in_flight: BTreeMap<u64, (Instant, Option<Instant>)>,
+    /// round trip and input to display (ms) for each frame drawn
+    round_trip: Vec<f32>,
+    input_to_display: Vec<f32>,
+}
+
+/// sequence number of last frame sent, by any slide
+static FRAME_SEQ: AtomicU64 = AtomicU64::new(0);
+
+impl Latency {
+    /// frames in flight kept, older frames are taken as lost
+    const MAX_IN_FLIGHT: usize = 256;
+    /// longest wait for frames in flight once a slide finishes
+    const SETTLE: Duration = Duration::from_millis(250);
+
+    fn new() -> Self {
+        Latency {
+            sent: 0,
+            pending_input: None,
+            in_flight: BTreeMap::new(),
+            round_trip: vec![],
+            input_to_display: vec![],
+        }
+    }
+
+    /// input was received
+    fn input(&mut self, received: Instant) {
+        if self.pending_input.is_none() {
+            self.pending_input = Some(received);
+        }
+    }
+
+    /// number and timestamp a frame to be sent
+    fn frame(&mut self, data: msg::Message) -> msg::Message {
+        let sent = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+
+        let seq = FRAME_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+        self.sent = self.sent + 1;
+        self.in_flight.insert(seq, (Instant::now(), self.pending_input.take()));
+        if self.in_flight.len() > Latency::MAX_IN_FLIGHT {
+            let oldest = *self.in_flight.keys().next().unwrap();
+            self.in_flight.remove(&oldest);
+        }
+        msg::frame(data, seq, sent)
+    }
+
+    /// client has drawn a frame, draw is time (ms) it took from receiving to drawing it
+    fn drawn(&mut self, seq: u64, draw_ms: f32) {
+        if let Some((sent, input)) = self.in_flight.remove(&seq) {
+            let round_trip = sent.elapsed().as_secs_f32() * 1000.0;
+            self.round_trip.push(round_trip);
+
+            if let Some(input) = input {
+                let to_send = (sent - input).as_secs_f32() * 1000.0;
+                let to_client = f32::max(round_trip - draw_ms, 0.0) / 2.0;
+                self.input_to_display.push(to_send + to_client + draw_ms);
+            }
+        }
+    }
+
+    /// wait, briefly, for frames still in flight to be acknowledged, so a slide's
+    /// last frames are counted. Not if client has not acknowledged any frames, as
+    /// it may not support it. Other events are kept for the next slide.
+    fn settle(&mut self, events: &Events) {
+        if self.round_trip.is_empty() {
+            return;
+        }
+
+        let until = Instant::now() + Latency::SETTLE;
+        let mut kept = vec![];
+        while !self.in_flight.is_empty() {
+            match events.recv(Some(until)) {
+                Ok(Event::Message(data)) => {
+                    match msg::frameDrawn(data.clone()) {
+                        Ok((seq, draw_ms)) => self.drawn(seq, draw_ms),
+                        Err(_) => kept.push(Event::Message(data)),
+                    }
+                },
+                Ok(event) => kept.push(event),
+                Err(_) => break,
+            }
+        }
+
+        // kept events came before any still pending
+        let mut pending = events.pending.borrow_mut();
+        for event in kept.into_iter().rev() {
+            pending.push_front(event);
+        }
+    }
+
+    /// number of frames sent
+    pub fn frames_sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// number of frames acknowledged as drawn
+    pub fn frames_drawn(&self) -> usize {
+        self.round_trip.len()
+    }
+
+    /// mean round trip (ms)
+    pub fn round_trip_ms(&self) -> f32 {
+        mean(&self.round_trip)
+    }
+
+    /// mean estimated input to display (ms)
+    pub fn input_to_display_ms(&self) -> f32 {
+        mean(&self.input_to_display)
+    }
+
+    /// largest estimated input to display (ms)
+    pub fn max_input_to_display_ms(&self) -> f32 {
+        self.input_to_display.iter().cloned().fold(0.0, f32::max)
+    }
+}
+
+fn mean(values: &Vec<f32>) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn clock_on_schedule() {
+        let mut clock = Clock::new(ms(10));
+        let start = clock.last;
+        for i in 1..=5 {
+            let dt = clock.tick(start + ms(10 * i));
+            assert!((dt - 0.01).abs() < 1e-6);
+        }
+        assert_eq!(clock.frames(), 5);
+        assert_eq!(clock.missed(), 0);
+        assert!((clock.mean_ms() - 10.0).abs() < 1e-3);
+        assert!(clock.jitter_ms() < 1e-3);
+        assert_eq!(clock.next, start + ms(60));
+    }
+
+    #[test]
+    fn clock_counts_missed_frames() {
+        let mut clock = Clock::new(ms(10));
+        let start = clock.last;
+        // frames due at 10, 20 and 30 ms, ticked late at 35 ms
+        clock.tick(start + ms(35));
+        assert_eq!(clock.frames(), 1);
+        assert_eq!(clock.missed(), 2);
+        // schedule is kept, next frame is due at 40 ms
+        assert_eq!(clock.next, start + ms(40));
+        clock.tick(start + ms(40));
+        assert_eq!(clock.missed(), 2);
+        assert!((clock.mean_ms() - 20.0).abs() < 1e-3);
+        assert!((clock.jitter_ms() - 15.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn clock_pause_is_not_missed() {
+        let mut clock = Clock::new(ms(10));
+        let start = clock.last;
+        clock.tick(start + ms(10));
+        clock.paused(ms(1000));
+        let dt = clock.tick(start + ms(1020));
+        assert_eq!(clock.missed(), 0);
+        assert!((dt - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn latency_seq_unique_across_slides() {
+        let mut first = Latency::new();
+        let mut second = Latency::new();
+        let a = first.frame(json!({"type": "press"}))["seq"].as_u64().unwrap();
+        let b = second.frame(json!({"type": "press"}))["seq"].as_u64().unwrap();
+        assert!(a != b);
+
+        // late acknowledgement of previous slide's frame is ignored
+        second.drawn(a, 1.0);
+        assert_eq!(second.frames_drawn(), 0);
+        second.drawn(b, 1.0);
+        assert_eq!(second.frames_drawn(), 1);
+        assert_eq!(second.frames_sent(), 1);
+    }
+
+    #[test]
+    fn latency_input_to_display() {
+        let mut latency = Latency::new();
+        let now = Instant::now();
+        // input 10 ms before frame was sent, acknowledged after 20 ms, of which 4 ms drawing
+        latency.in_flight.insert(1, (now - ms(20), Some(now - ms(30))));
+        latency.in_flight.insert(2, (now - ms(20), None));
+        latency.drawn(1, 4.0);
+        latency.drawn(2, 4.0);
+
+        assert_eq!(latency.frames_drawn(), 2);
+        assert!((latency.round_trip_ms() - 20.0).abs() < 5.0);
+        // 10 ms to send, (20 - 4) / 2 ms to client, 4 ms to draw
+        assert!((latency.input_to_display_ms() - 22.0).abs() < 5.0);
+        assert_eq!(latency.input_to_display_ms(), latency.max_input_to_display_ms());
+    }
+
+    #[test]
+    fn latency_in_flight_bounded() {
+        let mut latency = Latency::new();
+        for _ in 0..Latency::MAX_IN_FLIGHT + 10 {
+            latency.frame(json!({"type": "press"}));
+        }
+        assert_eq!(latency.in_flight.len(), Latency::MAX_IN_FLIGHT);
+        assert_eq!(latency.frames_sent(), Latency::MAX_IN_FLIGHT as u64 + 10);
+    }
+
+    #[test]
+    fn clock_without_frames() {
+        let clock = Clock::new(ms(10));
+        assert_eq!(clock.frames(), 0);
+        assert_eq!(clock.mean_ms(), 0.0);
+        assert_eq!(clock.jitter_ms(), 0.0);
+        assert_eq!(clock.interval_ms(), 10.0);
+    }
+}
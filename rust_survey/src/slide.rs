@@ -5,18 +5,27 @@
 //! 
 
 use rand::Rng;
+use rand::seq::SliceRandom;
 
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::sync::mpsc::{channel, Sender, Receiver};
 
 use crate::world::*;
 
 use crate::msg;
+use crate::metrics;
+use crate::task;
+use crate::runtime;
+use crate::runtime::{Wait, wait_msg};
 
 use crate::ws_server;
 use crate::world;
 
 use std::io::prelude::*;
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 
 //-----------------------------------------------------------------------------
 // Utils
@@ -26,7 +35,7 @@ fn round (f: f32) -> f32 {
     f.floor() + 0.5
 }
 
-fn range(output_start: f32, output_end: f32, input_start: f32, input_end: f32, input: f32) -> f32 {
+pub fn range(output_start: f32, output_end: f32, input_start: f32, input_end: f32, input: f32) -> f32 {
     let slope = 1.0 * (output_end - output_start) / (input_end - input_start);
     output_start + round(slope * (input - input_start))
 } 
@@ -35,12 +44,41 @@ fn range(output_start: f32, output_end: f32, input_start: f32, input_end: f32, i
 // SLIDES
 //-----------------------------------------------------------------------------
 
+/// How a slide finished
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// slide ran to completion
+    Completed,
+    /// slide did not complete before its timeout
+    TimedOut,
+    /// experimenter skipped slide
+    Skipped,
+    /// experimenter asked for slide to be run again
+    Repeated,
+    /// session ended early, by experimenter or because client went away
+    Aborted,
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Completed => "completed",
+            Outcome::TimedOut => "timed_out",
+            Outcome::Skipped => "skipped",
+            Outcome::Repeated => "repeated",
+            Outcome::Aborted => "aborted",
+        }
+    }
+}
+
+/// A slide runs until it completes, its deadline (if any) passes, or the
+/// experimenter interrupts it
 pub trait Slide {
     fn run(&self,
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>);
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome;
 }
 
 //-----------------------------------------------------------------------------
@@ -48,6 +86,7 @@ pub trait Slide {
 //-----------------------------------------------------------------------------
 
 /// Front page of survey presentation
+#[derive(Deserialize)]
 pub struct FrontMatter {
 }
 
@@ -62,116 +101,458 @@ impl FrontMatter {
 impl Slide for FrontMatter {
     fn run(&self, 
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
 
         // jump to frontmatter page
         outbound_msg.send(msg::gotoFrontMatter());
 
         // wait for user to press begin
         loop {
-            match inbound_msg.recv() {
-                Ok(data) => {
+            match wait_msg(events, deadline) {
+                Wait::Message(data) => {
                     if msg::is_begin(data) {
-                        return;
+                        return Outcome::Completed;
                     }
                 },
-                _ => {},
+                Wait::Interrupted(outcome) => return outcome,
             }
         }
     }
 }
 
-/// Front page of survey presentation
+/// Consent page of survey presentation. Consent text is read from a versioned file, 
+/// and a record of the version agreed to is kept with the participant's data.
+#[derive(Deserialize)]
 pub struct Consent {
+    /// path of consent text (HTML)
+    text: String,
+    /// version of consent text, e.g. as given in ethics approval
+    version: String,
 }
 
 impl Consent {
-    pub fn new() -> Self {
+    pub fn new(text: String, version: String) -> Self {
         Consent {
+            text: text,
+            version: version,
         }
     }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        std::fs::read_to_string(&self.text)
+            .map(|_| ())
+            .map_err(|e| format!("unable to read consent text '{}': {}", self.text, e))
+    }
 }
 
 impl Slide for Consent {
     fn run(&self, 
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // read consent text, hash is recorded so it is known exactly what was agreed to. 
+        // Text is checked when survey is loaded, but without it consent cannot be given
+        let text = match std::fs::read_to_string(&self.text) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("unable to read consent text '{}': {}", self.text, e);
+                return Outcome::Aborted;
+            },
+        };
+        let hash = format!("{:x}", Sha256::digest(text.as_bytes()));
 
         // jump to consent page
         outbound_msg.send(msg::consentID(world.create_id()));
+        outbound_msg.send(msg::consentText(self.version.clone(), text));
         outbound_msg.send(msg::gotoConsent());
 
-        // wait for user to press begin
+        // wait for user to agree or decline
         loop {
-            match inbound_msg.recv() {
-                Ok(data) => {
-                    if msg::is_consent(data) {
-                        return;
+            match wait_msg(events, deadline) {
+                Wait::Message(data) => {
+                    if msg::is_consent(data.clone()) {
+                        let timestamp = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map(|d| d.as_millis())
+                            .unwrap_or(0);
+                        world.writeMetadata("consent", json!({
+                            "version": self.version,
+                            "text": self.text,
+                            "sha256": hash,
+                            "timestamp": timestamp,
+                        }));
+                        return Outcome::Completed;
+                    }
+                    else if msg::is_decline(data) {
+                        // nothing is to be collected, so remove anything written so far and end session
+                        info!("participant declined consent");
+                        world.discard();
+                        outbound_msg.send(msg::gotoDeclined());
+                        return Outcome::Completed;
                     }
                 },
-                _ => {},
+                Wait::Interrupted(outcome @ Outcome::TimedOut) | Wait::Interrupted(outcome @ Outcome::Skipped) => {
+                    // consent was not given, so treat as declined, as without it nothing
+                    // may be collected
+                    info!("consent {}", outcome.name());
+                    world.discard();
+                    outbound_msg.send(msg::gotoDeclined());
+                    return outcome;
+                },
+                Wait::Interrupted(outcome) => return outcome,
             }
         }
     }
 }
 
 
+//-----------------------------------------------------------------------------
+// Ratings
+//
+// Likert, visual analogue scale and semantic differential slides all share the 
+// same Likert page, protocol and storage, differing only in the kind of scale. 
+//-----------------------------------------------------------------------------
+
+/// A single rated item, name is reported back by client and text is shown to user. 
+/// Left and right anchors are only used by semantic differential items.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LikertItem {
+    pub name: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right: Option<String>,
+}
+
+impl LikertItem {
+    fn new(name: &str, text: &str) -> Self {
+        LikertItem {
+            name: name.to_string(),
+            text: text.to_string(),
+            left: None,
+            right: None,
+        }
+    }
+}
+
+/// check an answer is for one of items and within min..=max
+fn validate_rating(items: &Vec<LikertItem>, min: u32, max: u32, likert: &msg::Likert) -> Result<(), String> {
+    if !items.iter().any(|item| item.name == likert.name) {
+        return Err(format!("unknown item '{}'", likert.name));
+    }
+    if likert.value < min || likert.value > max {
+        return Err(format!("value {} not in {}..{}", likert.value, min, max));
+    }
+    Ok(())
+}
+
+/// present items on the Likert page and collect answers, with latency (ms) from 
+/// slide being shown, keyed by item name. An item can be revised any number of 
+/// times, until user confirms with all items answered. If the slide is interrupted
+/// the outcome is returned instead and nothing should be recorded.
+fn collect_ratings(
+    material: u32,
+    gesture: &str,
+    definition: msg::Message,
+    items: &Vec<LikertItem>,
+    validate: &dyn Fn(&msg::Likert) -> Result<(), String>,
+    outbound_msg: &ws_server::WSServer, 
+    events: &runtime::Events,
+    deadline: Option<Instant>) -> Result<HashMap<String, (msg::Likert, u128)>, Outcome> {
+
+    // set material, gesture and items to be rated
+    outbound_msg.send(msg::materialIndex(material, msg::likert_num()));
+    outbound_msg.send(msg::gestureType(gesture.to_string()));
+    outbound_msg.send(definition);
+
+    // jump to likert page
+    outbound_msg.send(msg::gotoLikert());
+
+    let shown = Instant::now();
+
+    let mut answers: HashMap<String, (msg::Likert, u128)> = HashMap::new();
+    loop {
+        match wait_msg(events, deadline) {
+            Wait::Message(data) => {
+                if msg::is_likert_confirm(data.clone()) {
+                    let missing: Vec<String> = items
+                        .iter()
+                        .filter(|item| !answers.contains_key(&item.name))
+                        .map(|item| item.name.clone())
+                        .collect();
+
+                    if missing.is_empty() {
+                        return Ok(answers);
+                    }
+                    outbound_msg.send(msg::likertIncomplete(missing));
+                }
+                else {
+                    match msg::likert(data) {
+                        Ok(l) => {
+                            match validate(&l) {
+                                Ok(()) => {
+                                    answers.insert(l.name.clone(), (l, shown.elapsed().as_millis()));
+                                },
+                                Err(e) => {
+                                    error!("{}", e);
+                                    outbound_msg.send(msg::likertInvalid(l.name, e));
+                                }
+                            }
+                        },
+                        _ => {}
+                    }
+                }
+            },
+            Wait::Interrupted(outcome) => return Err(outcome),
+        }
+    }
+}
+
+/// Likert page of survey presentation
+#[derive(Deserialize)]
 pub struct Likert {
+    /// material index
     material: u32,
+    /// gesture material was used with
     gesture: String,
+    /// items to be rated
+    #[serde(default = "Likert::default_items")]
+    items: Vec<LikertItem>,
+    /// number of points on scale
+    #[serde(default = "Likert::default_scale")]
+    scale: u32,
+    /// label for each point on scale
+    #[serde(default = "Likert::default_anchors")]
+    anchors: Vec<String>,
 }
 
 impl Likert {
-    pub fn new(material: u32, gesture: String) -> Self {
+    pub fn new(material: u32, gesture: String, items: Vec<LikertItem>, scale: u32, anchors: Vec<String>) -> Self {
         Likert {
             material: material,
             gesture: gesture,
+            items: items,
+            scale: scale,
+            anchors: anchors,
+        }
+    }
+
+    fn default_items() -> Vec<LikertItem> {
+        vec![
+            LikertItem::new("accuracy", "Accuracy"),
+            LikertItem::new("comfort", "Comfort"),
+            LikertItem::new("responsivity", "Responsivity"),
+        ]
+    }
+
+    fn default_scale() -> u32 {
+        5
+    }
+
+    fn default_anchors() -> Vec<String> {
+        ["Strongly Disagree", "Disagree", "Neutral", "Agree", "Strongly Agree"]
+            .iter().map(|s| s.to_string()).collect()
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.items.is_empty() {
+            return Err("likert slide has no items".to_string());
+        }
+        if self.scale < 2 {
+            return Err(format!("likert scale must have at least 2 points, found {}", self.scale));
         }
+        if self.anchors.len() != self.scale as usize {
+            return Err(format!(
+                "likert scale has {} points, but {} anchors", self.scale, self.anchors.len()));
+        }
+        Ok(())
     }
 }
 
 impl Slide for Likert {
     fn run(&self, 
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        let mut answers = match collect_ratings(
+            self.material,
+            &self.gesture,
+            msg::likertDefinition("likert", &self.items, self.scale, &self.anchors),
+            &self.items,
+            &|l| validate_rating(&self.items, 1, self.scale, l),
+            outbound_msg,
+            events,
+            deadline) {
+            Ok(answers) => answers,
+            Err(outcome) => return outcome,
+        };
+
+        // exactly one row per item, in the order items were defined
+        for item in &self.items {
+            if let Some((l, latency)) = answers.remove(&item.name) {
+                let category = self.anchors[(l.value-1) as usize].clone();
+                world.writeLikert(&self.gesture, &self.material.to_string(), l, &category, latency);
+            }
+        }
+        Outcome::Completed
+    }
+}
+
+/// Visual analogue scale page of survey presentation, each item is answered on a 
+/// continuous scale from 0 to 100
+#[derive(Deserialize)]
+pub struct Vas {
+    /// material index
+    material: u32,
+    /// gesture material was used with
+    gesture: String,
+    /// items to be rated
+    items: Vec<LikertItem>,
+    /// labels for 0 and 100 ends of scale
+    anchors: (String, String),
+}
 
-        // set material and gesture
-        outbound_msg.send(msg::materialIndex(self.material, msg::likert_num()));
-        outbound_msg.send(msg::gestureType(self.gesture.clone()));
+impl Vas {
+    const MAX: u32 = 100;
 
-        // jump to likert page
-        outbound_msg.send(msg::gotoLikert());
+    pub fn new(material: u32, gesture: String, items: Vec<LikertItem>, anchors: (String, String)) -> Self {
+        Vas {
+            material: material,
+            gesture: gesture,
+            items: items,
+            anchors: anchors,
+        }
+    }
 
-        // now wait for Likert response (3 likert messages expected)
-        let mut msgs_received = 3;
-        loop {
-            match inbound_msg.recv() {
-                Ok(data) => {
-                    match msg::likert(data) {
-                        Ok(l) => {
-                            world.writeLikert(&self.gesture, &self.material.to_string(), l);
-                            msgs_received = msgs_received - 1;
-                        },
-                        _ => {}
-                    }
-                },
-                _ => {},
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.items.is_empty() {
+            return Err("visual analogue scale slide has no items".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Slide for Vas {
+    fn run(&self, 
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        let anchors = vec![self.anchors.0.clone(), self.anchors.1.clone()];
+        let mut answers = match collect_ratings(
+            self.material,
+            &self.gesture,
+            msg::likertDefinition("vas", &self.items, Vas::MAX, &anchors),
+            &self.items,
+            &|l| validate_rating(&self.items, 0, Vas::MAX, l),
+            outbound_msg,
+            events,
+            deadline) {
+            Ok(answers) => answers,
+            Err(outcome) => return outcome,
+        };
+
+        for item in &self.items {
+            if let Some((l, latency)) = answers.remove(&item.name) {
+                world.writeLikert(&self.gesture, &self.material.to_string(), l, "VAS", latency);
             }
+        }
+        Outcome::Completed
+    }
+}
+
+/// Semantic differential page of survey presentation, each item is a pair of 
+/// opposing adjectives (e.g. rough and smooth) with a scale between them
+#[derive(Deserialize)]
+pub struct SemanticDifferential {
+    /// material index
+    material: u32,
+    /// gesture material was used with
+    gesture: String,
+    /// items to be rated, each must have left and right anchors
+    items: Vec<LikertItem>,
+    /// number of points on scale
+    #[serde(default = "SemanticDifferential::default_scale")]
+    scale: u32,
+}
+
+impl SemanticDifferential {
+    pub fn new(material: u32, gesture: String, items: Vec<LikertItem>, scale: u32) -> Self {
+        SemanticDifferential {
+            material: material,
+            gesture: gesture,
+            items: items,
+            scale: scale,
+        }
+    }
+
+    fn default_scale() -> u32 {
+        7
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.items.is_empty() {
+            return Err("semantic differential slide has no items".to_string());
+        }
+        if self.scale < 2 {
+            return Err(format!("semantic differential scale must have at least 2 points, found {}", self.scale));
+        }
+        match self.items.iter().find(|item| item.left.is_none() || item.right.is_none()) {
+            Some(item) => Err(format!("semantic differential item '{}' needs left and right anchors", item.name)),
+            None => Ok(()),
+        }
+    }
+}
 
-            if msgs_received == 0 {
-                return;
+impl Slide for SemanticDifferential {
+    fn run(&self, 
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        let mut answers = match collect_ratings(
+            self.material,
+            &self.gesture,
+            msg::likertDefinition("differential", &self.items, self.scale, &vec![]),
+            &self.items,
+            &|l| validate_rating(&self.items, 1, self.scale, l),
+            outbound_msg,
+            events,
+            deadline) {
+            Ok(answers) => answers,
+            Err(outcome) => return outcome,
+        };
+
+        for item in &self.items {
+            if let Some((l, latency)) = answers.remove(&item.name) {
+                // anchors are checked when survey is loaded
+                let category = format!(
+                    "{}/{}", 
+                    item.left.clone().unwrap_or_default(), 
+                    item.right.clone().unwrap_or_default());
+                world.writeLikert(&self.gesture, &self.material.to_string(), l, &category, latency);
             }
         }
+        Outcome::Completed
     }
 }
 
 /// Press page of survey presentation
+#[derive(Deserialize)]
 pub struct Press {
     material: u32,
     duration: u64,
@@ -187,6 +568,9 @@ impl Press {
     const INPUT_START: f32  = 20.0;
     const INPUT_END: f32    = 1500.0;
 
+    /// time (ms) circle must stay on ring for, before ring moves
+    const HOLD: u64 = 1000;
+
     pub fn new(material: u32, duration: u64, tolerance: f32) -> Self {
         Press {
             material: material,
@@ -194,108 +578,58 @@ impl Press {
             tolerance: tolerance,
         }
     }
-}
 
+    /// pressure sets circle radius, which must be held on a randomly placed ring
+    fn task(&self) -> task::Task {
+        task::Task::new(
+            "press".to_string(),
+            self.material,
+            self.duration,
+            task::InputMap {
+                axis: task::Axis::Pressure,
+                input: (Press::INPUT_START, Press::INPUT_END),
+                output: (Press::OUTPUT_START, Press::OUTPUT_END),
+            },
+            task::TargetGen::Random { min: Press::OUTPUT_RING_MIN, max: Press::OUTPUT_END },
+            0.0,
+            self.tolerance,
+            Some(task::Success { hold: Press::HOLD }),
+            task::View::Press,
+            false)
+    }
+}
 
 impl Slide for Press { 
     fn run(&self, 
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
-
-        // jump to frontmatter page
-        outbound_msg.send(msg::materialIndex(self.material, msg::press_num()));
-        outbound_msg.send(msg::gotoPress());
-
-        let mut rng = rand::thread_rng();
-
-        let circle_radius     = Press::OUTPUT_START;
-        let mut ring_radius   = rng.gen_range(Press::OUTPUT_RING_MIN, Press::OUTPUT_END);
-
-        outbound_msg.send(msg::press(circle_radius, ring_radius));
-
-        // track if touch is causing circle radius ~ ring radius, within a given tolerance
-        let mut tolerance_timer = Instant::now();
-        let mut within_tolerance = false;
-
-        let overall_timer  = Instant::now();
-        
-        let mut data: Vec<world::Contacts> = vec![vec![]]; 
-        let mut circle_ring_radius: Vec<(f32, f32)> = vec![(circle_radius, ring_radius)];
-        let mut num_presses = 0;
-
-        // pressure input, until time is done
-        while overall_timer.elapsed().as_secs() < self.duration {
-            
-            match inbound_osc.try_recv() {
-                Ok((input_type, pressure, x, y, material)) => {
-                    if material == self.material {
-                        // map pressure into range and then send radius to frontend
-                        let circle_radius = range(
-                            Press::OUTPUT_START, 
-                            Press::OUTPUT_END, 
-                            Press::INPUT_START, 
-                            Press::INPUT_END, 
-                            pressure);
-                            
-                        data[num_presses].push((overall_timer.elapsed().as_millis(), pressure, x, y));
-
-                        // is circle radius ~ ring radius
-                        if (ring_radius - circle_radius).abs() <= self.tolerance {
-                            if within_tolerance {
-                                if tolerance_timer.elapsed().as_secs() >= 1 {
-                                    ring_radius = rng.gen_range(Press::OUTPUT_RING_MIN, Press::OUTPUT_END);
-                                    within_tolerance = false;
-
-                                    // update press storage
-                                    num_presses = num_presses + 1;
-                                    data.push(vec![]);
-                                    circle_ring_radius.push((circle_radius, ring_radius));
-                                }
-                            }
-                            else {
-                                within_tolerance = true;
-                                tolerance_timer = Instant::now();
-                            }
-                        }
-                        else {
-                            // need to make sure we reset seen tolerance if we fall out
-                            within_tolerance = false;
-                        }
-
-                        outbound_msg.send(msg::press(circle_radius, ring_radius));
-                    }
-                },
-                _ => {},
-            }
-        }
-        world.writeGesture("press".to_string(), self.material, circle_ring_radius, data);
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+        self.task().run(world, events, outbound_msg, deadline)
     }
 }
 
 //-----------------------------------------------------------------------------
-// Slider gesture
+// Slider and tap gestures
 //
-// The implementation is very similar to press, but we are now dealing with 
-// horx movements, rather than pressure, and the animation is different.
+// Both follow a box moving across the screen, with a user box placed by x 
+// position on the pad. Slider measures how well the box is tracked, while tap 
+// records each time the user box lands inside the box.
 //-----------------------------------------------------------------------------
 
 /// Slider page of survey presentation
+#[derive(Deserialize)]
 pub struct Slider {
     /// material index
     material: u32,
     /// duration to test
     duration: u64,
+    /// user box within tolerance of box is on it
     tolerance: f32,
     /// top left x of the pad
     top_left_x: f32,
-    /// top left y of the pad
-    top_left_y: f32,
     /// width of pad
     width: f32,
-    /// height of pad
-    height: f32,
 }
 
 impl Slider {
@@ -304,141 +638,148 @@ impl Slider {
     const USER_X_SIZE: f32 = 20.0;
     const BOX_X_SIZE:  f32 = 150.0;
     const MIN_BOX_X_SIZE: f32 = Slider::USER_X_SIZE * 3.0;  
-    const STEP_SIZE: f32   = 2.0;
+    /// speed of box, in units per second
+    const SPEED: f32       = 120.0;
+    /// shrink of box, and extra speed, in units per second while user box is on it
+    const SHRINK: f32      = 36.0;
+    const NUDGE: f32       = 30.0;
 
     pub fn new(
         material: u32, 
         duration: u64, 
         tolerance: f32,
         top_left_x: f32,
-        top_left_y: f32,
-        width: f32,
-        height: f32,) -> Self {
+        width: f32,) -> Self {
         Slider {
             material: material,
             duration: duration,
             tolerance: tolerance,
             top_left_x: top_left_x,
-            top_left_y: top_left_y,
             width: width,
-            height: height,
         }
     }
+
+    /// x across the pad places user box, which follows a moving box
+    fn box_task(
+        name: &str, 
+        material: u32, 
+        duration: u64, 
+        top_left_x: f32, 
+        width: f32, 
+        tolerance: f32,
+        success: Option<task::Success>,
+        view: task::View,
+        tracking: bool) -> task::Task {
+        task::Task::new(
+            name.to_string(),
+            material,
+            duration,
+            task::InputMap {
+                axis: task::Axis::X,
+                input: (top_left_x, top_left_x + width),
+                output: (Slider::MIN_X, Slider::MAX_X - Slider::USER_X_SIZE),
+            },
+            task::TargetGen::Bounce {
+                min: Slider::MIN_X,
+                max: Slider::MAX_X,
+                size: Slider::BOX_X_SIZE,
+                min_size: Slider::MIN_BOX_X_SIZE,
+                speed: Slider::SPEED,
+                shrink: Slider::SHRINK,
+                nudge: Slider::NUDGE,
+            },
+            Slider::USER_X_SIZE,
+            tolerance,
+            success,
+            view,
+            tracking)
+    }
 }
 
 impl Slide for Slider { 
     fn run(&self, 
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
-
-        // jump to frontmatter page
-        outbound_msg.send(msg::materialIndex(self.material, msg::slider_num()));
-        outbound_msg.send(msg::gotoSlider());
-
-        // random number generator for direction and placement of box
-        let mut rng = rand::thread_rng();
-
-        let mut direction: f32 = rng.gen();
-        direction = if direction > 90.0 { Slider::STEP_SIZE } else { -1.0 * Slider::STEP_SIZE };
-
-        let mut user_x = rng.gen_range(Slider::MIN_X, Slider::MAX_X-Slider::USER_X_SIZE);
-        
-        let mut box_x = (rng.gen_range(Slider::MIN_X, Slider::MAX_X-Slider::BOX_X_SIZE) as u32) as f32;
-        box_x = Slider::MIN_X;
-        let mut box_size = Slider::BOX_X_SIZE;
-
-        // place box and user box in initial positions 
-        outbound_msg.send(msg::slider(user_x, box_x, box_size));
-
-        // track if touch is causing circle radius ~ ring radius, within a given tolerance
-        let mut tolerance_timer = Instant::now();
-        let mut within_tolerance = false;
-
-        // timer for time stamps outputs
-        let overall_timer  = Instant::now();
-        
-        let mut data: Vec<world::Contacts> = vec![vec![]]; 
-        let mut box_details: Vec<(f32, f32)> = vec![(box_x, box_size)];
-        let mut num_boxes = 0;
-
-        // timer to control animation FPS
-        let mut animation_timer = Instant::now();
-
-        // time animation and responses
-        while overall_timer.elapsed().as_secs() < self.duration {   
-            match inbound_osc.try_recv() {
-                Ok((input_type, pressure, x, y, material)) => {
-                    if material == self.material {
-                        // update user box postion with respect to sensel input
-                        user_x = range(
-                            Slider::MIN_X,
-                            Slider::MAX_X - Slider::USER_X_SIZE,
-                            self.top_left_x,
-                            self.top_left_x + self.width, 
-                            x);
-
-                        data[num_boxes].push((overall_timer.elapsed().as_millis(), pressure, x, y));
-
-                        // is the user box inside the box?
-                        if user_x >= box_x  && user_x+Slider::USER_X_SIZE <= box_x + box_size {
-                            box_size = f32::max(box_size - 0.6, Slider::MIN_BOX_X_SIZE); 
-                            box_x = box_x + if direction < 0.0 { -0.5 } else { 0.5 };
-                            //box_x    = rng.gen_range(Slider::MIN_X, Slider::MAX_X - box_size);
-
-                            //rng.gen_range(Slider::USER_X_SIZE, Slider::BOX_X_SIZE);
-                            //box_x    = rng.gen_range(Slider::MIN_X, Slider::MAX_X - Slider::BOX_X_SIZE);
-                            // update box storage
-                            num_boxes = num_boxes + 1;
-                            data.push(vec![]);
-                            box_details.push((box_x, box_size));
-                        }
-                    }
-                },
-                _ => {}
-            }
-
-            // update box and its direction @60hz
-            if animation_timer.elapsed().as_millis() > 16 {
-                box_x = box_x + direction;
-
-                // clamp within animation range and change direction of box if at boundary
-                if box_x + box_size >= Slider::MAX_X  {
-                    direction = -1.0 * Slider::STEP_SIZE;
-                    box_x = Slider::MAX_X - box_size;
-                }
-                else if box_x <= Slider::MIN_X {
-                    direction = Slider::STEP_SIZE;
-                    box_x = Slider::MIN_X;
-                }
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+        Slider::box_task("slider", self.material, self.duration, self.top_left_x, self.width, self.tolerance, None, task::View::Slider, true)
+            .run(world, events, outbound_msg, deadline)
+    }
+}
 
-                // reset timer
-                animation_timer = Instant::now();
+/// Tap page of survey presentation
+#[derive(Deserialize)]
+pub struct Tap {
+    /// material index
+    material: u32,
+    /// duration to test
+    duration: u64,
+    /// user box within tolerance of box is on it
+    tolerance: f32,
+    /// top left x of the pad
+    top_left_x: f32,
+    /// width of pad
+    width: f32,
+}
 
-                // update view
-                outbound_msg.send(msg::slider(user_x, box_x, box_size));
-            }            
+impl Tap {
+    pub fn new(
+        material: u32, 
+        duration: u64, 
+        tolerance: f32,
+        top_left_x: f32,
+        width: f32,) -> Self {
+        Tap {
+            material: material,
+            duration: duration,
+            tolerance: tolerance,
+            top_left_x: top_left_x,
+            width: width,
         }
+    }
+}
 
-        world.writeGesture("slider".to_string(), self.material, box_details, data);
+impl Slide for Tap { 
+    fn run(&self, 
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+        let success = Some(task::Success { hold: 0 });
+        Slider::box_task("tap", self.material, self.duration, self.top_left_x, self.width, self.tolerance, success, task::View::Tap, false)
+            .run(world, events, outbound_msg, deadline)
     }
 }
 
 //-----------------------------------------------------------------------------
-// Tap gesture
+// 2D target acquisition and tracking
 //
-// The implementation is very similar to press, but we are now dealing with 
-// horx movements, rather than pressure, and the animation is different.
+// Unlike slider and tap, both x and y of a touch are used to place a cursor 
+// on a 2D canvas, which the user either moves to a sequence of targets, or 
+// uses to follow a target moving between them.
 //-----------------------------------------------------------------------------
 
-/// Slider page of survey presentation
-pub struct Tap {
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target2DMode {
+    /// move cursor onto each target in turn, staying on it for dwell ms
+    Acquire { dwell: u64 },
+    /// follow a single target moving through each target (as waypoints) in turn, 
+    /// at speed canvas units per second
+    Track { speed: f32 },
+}
+
+/// 2D target page of survey presentation
+#[derive(Deserialize)]
+pub struct Target2D {
     /// material index
     material: u32,
     /// duration to test
     duration: u64,
-    tolerance: f32,
+    /// acquisition or tracking
+    mode: Target2DMode,
+    /// targets, in canvas coordinates
+    targets: Vec<Target>,
     /// top left x of the pad
     top_left_x: f32,
     /// top left y of the pad
@@ -449,146 +790,913 @@ pub struct Tap {
     height: f32,
 }
 
-impl Tap {
-    const MIN_X: f32       = 50.0;
-    const MAX_X: f32       = 650.0;
-    const USER_X_SIZE: f32 = 20.0;
-    const BOX_X_SIZE:  f32 = 150.0;
-    const MIN_BOX_X_SIZE: f32 = Tap::USER_X_SIZE * 3.0;  
-    const STEP_SIZE: f32   = 2.0;
+impl Target2D {
+    const MIN_X: f32 = 20.0;
+    const MAX_X: f32 = 730.0;
+    const MIN_Y: f32 = 20.0;
+    const MAX_Y: f32 = 380.0;
 
     pub fn new(
-        material: u32, 
-        duration: u64, 
-        tolerance: f32,
+        material: u32,
+        duration: u64,
+        mode: Target2DMode,
+        targets: Vec<Target>,
         top_left_x: f32,
         top_left_y: f32,
         width: f32,
         height: f32,) -> Self {
-        Tap {
+        Target2D {
             material: material,
             duration: duration,
-            tolerance: tolerance,
+            mode: mode,
+            targets: targets,
             top_left_x: top_left_x,
             top_left_y: top_left_y,
             width: width,
             height: height,
         }
     }
-}
 
-impl Slide for Tap { 
-    fn run(&self, 
-        world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
+    /// map a pad position into canvas coordinates
+    fn cursor(&self, x: f32, y: f32) -> (f32, f32) {
+        (range(Target2D::MIN_X, Target2D::MAX_X, self.top_left_x, self.top_left_x + self.width, x),
+         range(Target2D::MIN_Y, Target2D::MAX_Y, self.top_left_y, self.top_left_y + self.height, y))
+    }
 
-        // jump to frontmatter page
-        outbound_msg.send(msg::materialIndex(self.material, msg::tap_num()));
-        outbound_msg.send(msg::gotoTap());
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.targets.is_empty() {
+            return Err("2D target slide has no targets".to_string());
+        }
+        if self.targets.iter().any(|t| !(t.size > 0.0)) {
+            return Err("2D target size must be greater than 0".to_string());
+        }
+        if !(self.width > 0.0 && self.height > 0.0) {
+            return Err("2D target pad width and height must be greater than 0".to_string());
+        }
+        if let Target2DMode::Track { speed } = self.mode {
+            if !(speed >= 0.0) {
+                return Err("2D target tracking speed must not be negative".to_string());
+            }
+        }
+        Ok(())
+    }
 
-        // random number generator for direction and placement of box
-        let mut rng = rand::thread_rng();
+    /// is cursor within target
+    fn on_target(cursor: (f32, f32), target: &Target) -> bool {
+        let dx = cursor.0 - target.x;
+        let dy = cursor.1 - target.y;
+        (dx * dx + dy * dy).sqrt() <= target.size / 2.0
+    }
 
-        let mut direction: f32 = rng.gen();
-        direction = if direction > 90.0 { Tap::STEP_SIZE } else { -1.0 * Tap::STEP_SIZE };
-
-        let mut user_x = rng.gen_range(Tap::MIN_X, Tap::MAX_X-Tap::USER_X_SIZE);
-        
-        let mut box_x = (rng.gen_range(Tap::MIN_X, Tap::MAX_X-Tap::BOX_X_SIZE) as u32) as f32;
-        box_x = Tap::MIN_X;
-        let mut box_size = Tap::BOX_X_SIZE;
-
-        // place box and user box in initial positions 
-        outbound_msg.send(msg::tap(user_x, box_x));
-
-        // track if touch is causing circle radius ~ ring radius, within a given tolerance
-        let mut tolerance_timer = Instant::now();
-        let mut within_tolerance = false;
-
-        // timer for time stamps outputs
-        let overall_timer  = Instant::now();
-        
-        let mut data: Vec<world::Contacts> = vec![vec![]]; 
-        let mut box_details: Vec<(f32, f32)> = vec![(box_x, box_size)];
-        let mut num_boxes = 0;
-
-        // timer to control animation FPS
-        let mut animation_timer = Instant::now();
-
-        // time animation and responses
-        while overall_timer.elapsed().as_secs() < self.duration {   
-            match inbound_osc.try_recv() {
-                Ok((input_type, pressure, x, y, material)) => {
-                    if material == self.material {
-                        // update user box postion with respect to sensel input
-                        user_x = range(
-                            Tap::MIN_X,
-                            Tap::MAX_X - Tap::USER_X_SIZE,
-                            self.top_left_x,
-                            self.top_left_x + self.width, 
-                            x);
-
-                        data[num_boxes].push((overall_timer.elapsed().as_millis(), pressure, x, y));
-
-                        // is the user box inside the box?
-                        if user_x >= box_x  && user_x+Tap::USER_X_SIZE <= box_x + box_size {
-                            box_size = f32::max(box_size - 0.6, Tap::MIN_BOX_X_SIZE); 
-                            box_x = box_x + if direction < 0.0 { -0.5 } else { 0.5 };
-                            //box_x    = rng.gen_range(Tap::MIN_X, Tap::MAX_X - box_size);
-
-                            //rng.gen_range(Tap::USER_X_SIZE, Tap::BOX_X_SIZE);
-                            //box_x    = rng.gen_range(Tap::MIN_X, Tap::MAX_X - Tap::BOX_X_SIZE);
-                            // update box storage
-                            num_boxes = num_boxes + 1;
-                            data.push(vec![]);
-                            box_details.push((box_x, box_size));
-                        }
-                    }
-                },
-                _ => {}
+    /// position of moving target, after travelling distance along path through targets
+    fn along_path(&self, distance: f32) -> Target {
+        let segments: Vec<(Target, Target, f32)> = (0..self.targets.len())
+            .map(|i| {
+                let from = self.targets[i];
+                let to = self.targets[(i + 1) % self.targets.len()];
+                (from, to, ((to.x - from.x).powi(2) + (to.y - from.y).powi(2)).sqrt())
+            })
+            .collect();
+
+        let total: f32 = segments.iter().map(|s| s.2).sum();
+        if total <= 0.0 {
+            return self.targets[0];
+        }
+
+        // path loops back to first target
+        let mut d = distance % total;
+        for (from, to, length) in segments {
+            if d <= length && length > 0.0 {
+                let t = d / length;
+                return Target {
+                    x: from.x + t * (to.x - from.x),
+                    y: from.y + t * (to.y - from.y),
+                    size: from.size + t * (to.size - from.size),
+                };
             }
+            d = d - length;
+        }
+        self.targets[0]
+    }
+}
+
+/// State of a running 2D target slide
+struct Target2DTrial<'a> {
+    slide: &'a Target2D,
+    cursor: (f32, f32),
+    target_index: usize,
+    target: Target,
+    // timer for time stamps outputs
+    overall_timer: Instant,
+    // (target, time to acquire) and contacts, per target
+    data: Vec<world::Contacts>,
+    acquired: Vec<(Target, Option<u128>)>,
+    // acquisition mode, time target was presented and time cursor entered it
+    target_timer: Instant,
+    dwell_timer: Option<Instant>,
+    // tracking mode, only measured once user has touched
+    tracking: metrics::Tracking,
+    touched: bool,
+}
+
+impl<'a> runtime::Handler for Target2DTrial<'a> {
+    fn input(&mut self, input: msg::SenselMessage, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (_input_type, pressure, x, y, material, timestamp) = input;
+        if material == self.slide.material {
+            self.cursor = self.slide.cursor(x, y);
+            self.data[self.target_index].push((self.overall_timer.elapsed().as_millis(), pressure, x, y, timestamp));
+            self.touched = true;
+        }
+        None
+    }
 
-            // update box and its direction @60hz
-            if animation_timer.elapsed().as_millis() > 16 {
-                box_x = box_x + direction;
+    fn tick(&mut self, dt: f32, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        if self.overall_timer.elapsed().as_secs() >= self.slide.duration {
+            return Some(Outcome::Completed);
+        }
 
-                // clamp within animation range and change direction of box if at boundary
-                if box_x + box_size >= Tap::MAX_X  {
-                    direction = -1.0 * Tap::STEP_SIZE;
-                    box_x = Tap::MAX_X - box_size;
+        // update target and view
+        let on_target = Target2D::on_target(self.cursor, &self.target);
+
+        match self.slide.mode {
+            Target2DMode::Acquire { dwell } => {
+                if on_target {
+                    match self.dwell_timer {
+                        Some(timer) if timer.elapsed().as_millis() >= dwell as u128 => {
+                            // acquired, so move to next target
+                            self.acquired.push((self.target, Some(self.target_timer.elapsed().as_millis())));
+                            self.target_index = self.target_index + 1;
+                            if self.target_index == self.slide.targets.len() {
+                                return Some(Outcome::Completed);
+                            }
+                            self.target = self.slide.targets[self.target_index];
+                            self.data.push(vec![]);
+                            self.target_timer = Instant::now();
+                            self.dwell_timer = None;
+                        },
+                        None => {
+                            self.dwell_timer = Some(Instant::now());
+                        },
+                        _ => {},
+                    }
                 }
-                else if box_x <= Tap::MIN_X {
-                    direction = Tap::STEP_SIZE;
-                    box_x = Tap::MIN_X;
+                else {
+                    self.dwell_timer = None;
                 }
-
-                // reset timer
-                animation_timer = Instant::now();
-
-                // update view
-                outbound_msg.send(msg::tap(user_x, box_x));
-            }            
+            },
+            Target2DMode::Track { speed } => {
+                if self.touched {
+                    self.tracking.update_2d(self.cursor, (self.target.x, self.target.y), on_target, dt);
+                }
+                self.target = self.slide.along_path(speed * self.overall_timer.elapsed().as_secs_f32());
+            },
         }
+        None
+    }
+
+    fn render(&mut self) -> Option<msg::Message> {
+        Some(msg::target2D(self.cursor.0, self.cursor.1, self.target.x, self.target.y, self.target.size))
+    }
 
-        world.writeGesture("tap".to_string(), self.material, box_details, data);
+    fn paused(&mut self, duration: Duration) {
+        self.overall_timer = self.overall_timer + duration;
+        self.target_timer = self.target_timer + duration;
+        self.dwell_timer = self.dwell_timer.map(|timer| timer + duration);
     }
 }
 
+impl Slide for Target2D {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // jump to 2D target page
+        outbound_msg.send(msg::materialIndex(self.material, msg::target2d_num()));
+        outbound_msg.send(msg::gotoTarget2D());
+
+        // cursor starts in centre of canvas
+        let cursor = ((Target2D::MIN_X + Target2D::MAX_X) / 2.0, (Target2D::MIN_Y + Target2D::MAX_Y) / 2.0);
+        let target = self.targets[0];
+
+        outbound_msg.send(msg::target2D(cursor.0, cursor.1, target.x, target.y, target.size));
+
+        let mut trial = Target2DTrial {
+            slide: self,
+            cursor: cursor,
+            target_index: 0,
+            target: target,
+            overall_timer: Instant::now(),
+            data: vec![vec![]],
+            acquired: vec![],
+            target_timer: Instant::now(),
+            dwell_timer: None,
+            tracking: metrics::Tracking::new(),
+            touched: false,
+        };
+
+        let mut clock = events.clock();
+        let outcome = runtime::run(&mut trial, events, outbound_msg, deadline, Some(&mut clock));
+        world.writeFrameTiming("target2d".to_string(), self.material, &clock);
+
+        let mut data = trial.data;
+        match self.mode {
+            Target2DMode::Acquire { .. } => {
+                let mut acquired = trial.acquired;
+                // any targets not reached within duration are recorded as not acquired
+                if trial.target_index < self.targets.len() {
+                    acquired.push((trial.target, None));
+                    for t in &self.targets[trial.target_index+1..] {
+                        acquired.push((*t, None));
+                        data.push(vec![]);
+                    }
+                }
+                world.writeAcquisition("target2d".to_string(), self.material, acquired, data);
+            },
+            Target2DMode::Track { speed } => {
+                world.writeTargetTrack("target2d".to_string(), self.material, speed, &self.targets, &data[0]);
+                world.writeTracking("target2d".to_string(), self.material, &trial.tracking);
+            },
+        }
+        outcome
+    }
+}
 
 //-----------------------------------------------------------------------------
-// Case when users reports properties about one or more materials
+// Fitts' law pointing
+//
+// Targets, of a given distance and width, are presented in turn and the user
+// selects each one by lifting from the material. Positions are either 
+// reciprocal along x (1D), or around a circle (2D, as in ISO 9241-9). The 2D 
+// target view is reused for presentation.
 //-----------------------------------------------------------------------------
 
-pub struct Response {
-    // name of property to requested for material(s)
-    name: String,
-    // number of materials to be responded on
-    num_materials: u32,
-    // number of slide on client slide (required as there can be different response pages)
-    slide_num: u32,
-}
+/// Fitts' law page of survey presentation
+#[derive(Deserialize)]
+pub struct Fitts {
+    /// material index
+    material: u32,
+    /// maximum duration of test
+    duration: u64,
+    /// (distance, width) of each condition, in canvas units
+    conditions: Vec<(f32, f32)>,
+    /// number of selections per condition
+    selections: u32,
+    /// use both x and y, otherwise only x
+    two_d: bool,
+    /// top left x of the pad
+    top_left_x: f32,
+    /// top left y of the pad
+    top_left_y: f32,
+    /// width of pad
+    width: f32,
+    /// height of pad
+    height: f32,
+}
+
+impl Fitts {
+    const CENTRE_X: f32 = 375.0;
+    const CENTRE_Y: f32 = 200.0;
+
+    pub fn new(
+        material: u32,
+        duration: u64,
+        conditions: Vec<(f32, f32)>,
+        selections: u32,
+        two_d: bool,
+        top_left_x: f32,
+        top_left_y: f32,
+        width: f32,
+        height: f32,) -> Self {
+        Fitts {
+            material: material,
+            duration: duration,
+            conditions: conditions,
+            selections: selections,
+            two_d: two_d,
+            top_left_x: top_left_x,
+            top_left_y: top_left_y,
+            width: width,
+            height: height,
+        }
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.conditions.is_empty() {
+            return Err("fitts slide has no conditions".to_string());
+        }
+        if self.conditions.iter().any(|(distance, width)| !(*distance > 0.0 && *width > 0.0)) {
+            return Err("fitts condition distance and width must be greater than 0".to_string());
+        }
+        if self.selections == 0 {
+            return Err("fitts slide needs at least 1 selection per condition".to_string());
+        }
+        // targets, at CENTRE +/- distance / 2 in x, and also y for 2D, must be on canvas
+        for (distance, width) in &self.conditions {
+            let x = distance / 2.0 + width / 2.0;
+            let y = if self.two_d { x } else { width / 2.0 };
+            if Fitts::CENTRE_X - x < Target2D::MIN_X || Fitts::CENTRE_X + x > Target2D::MAX_X ||
+               Fitts::CENTRE_Y - y < Target2D::MIN_Y || Fitts::CENTRE_Y + y > Target2D::MAX_Y {
+                return Err(format!("fitts condition ({}, {}) has targets outside canvas", distance, width));
+            }
+        }
+        if !(self.width > 0.0 && self.height > 0.0) {
+            return Err("fitts pad width and height must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// target positions for a condition, the first is the starting target and 
+    /// is not part of the measured selections
+    fn positions(&self, distance: f32) -> Vec<(f32, f32)> {
+        let n = self.selections as usize + 1;
+        // odd number of targets around circle, as in ISO 9241-9
+        let targets = self.selections as usize | 1;
+        (0..n).map(|i| {
+            if self.two_d {
+                // each step crosses circle of diameter distance, to the target 
+                // (targets + 1) / 2 on, so every selection is the same distance
+                let index = (i * (targets + 1) / 2) % targets;
+                let angle = 2.0 * std::f32::consts::PI * index as f32 / targets as f32;
+                (Fitts::CENTRE_X + distance / 2.0 * angle.cos(), 
+                 Fitts::CENTRE_Y + distance / 2.0 * angle.sin())
+            }
+            else {
+                let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+                (Fitts::CENTRE_X + side * distance / 2.0, Fitts::CENTRE_Y)
+            }
+        }).collect()
+    }
+
+    /// map a pad position into canvas coordinates
+    fn cursor(&self, x: f32, y: f32) -> (f32, f32) {
+        let cursor_x = range(Target2D::MIN_X, Target2D::MAX_X, self.top_left_x, self.top_left_x + self.width, x);
+        if self.two_d {
+            (cursor_x, range(Target2D::MIN_Y, Target2D::MAX_Y, self.top_left_y, self.top_left_y + self.height, y))
+        }
+        else {
+            (cursor_x, Fitts::CENTRE_Y)
+        }
+    }
+}
+
+/// State of a running Fitts' law slide
+struct FittsRun<'a> {
+    slide: &'a Fitts,
+    overall_timer: Instant,
+    cursor: (f32, f32),
+    selections: Vec<metrics::FittsSelection>,
+    summaries: Vec<((f32, f32), metrics::FittsSummary)>,
+    // current condition, its target positions and selections so far
+    condition: usize,
+    positions: Vec<(f32, f32)>,
+    trials: Vec<metrics::FittsTrial>,
+    index: usize,
+    selection_timer: Instant,
+    // view to be rendered at next frame
+    view: Option<msg::Message>,
+    live: runtime::LiveStats,
+}
+
+impl<'a> FittsRun<'a> {
+    /// present first target of current condition
+    fn start_condition(&mut self) {
+        let (distance, width) = self.slide.conditions[self.condition];
+        self.positions = self.slide.positions(distance);
+        self.trials = vec![];
+        self.index = 0;
+        self.selection_timer = Instant::now();
+        self.view = Some(msg::target2D(self.cursor.0, self.cursor.1, self.positions[0].0, self.positions[0].1, width));
+    }
+
+    /// summarise current condition
+    fn end_condition(&mut self) {
+        let condition = self.slide.conditions[self.condition];
+        self.summaries.push((condition, metrics::FittsSummary::new(&self.trials)));
+    }
+}
+
+impl<'a> runtime::Handler for FittsRun<'a> {
+    fn input(&mut self, input: msg::SenselMessage, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (input_type, _pressure, x, y, material, _timestamp) = input;
+        if material != self.slide.material {
+            return None;
+        }
+
+        let (_distance, width) = self.slide.conditions[self.condition];
+
+        // lifting selects target, at last position of cursor
+        if input_type == msg::InputType::End {
+            let target = self.positions[self.index];
+            if self.index > 0 {
+                let from = self.positions[self.index - 1];
+                
+                // deviation of endpoint along axis from previous to current target, 
+                // whose length is the distance of the selection, for 2D a chord 
+                // slightly shorter than the condition's distance
+                let length = ((target.0 - from.0).powi(2) + (target.1 - from.1).powi(2)).sqrt();
+                let axis = ((target.0 - from.0) / length, (target.1 - from.1) / length);
+                let deviation = (self.cursor.0 - target.0) * axis.0 + (self.cursor.1 - target.1) * axis.1;
+
+                let hit = Target2D::on_target(self.cursor, &Target { x: target.0, y: target.1, size: width });
+                let trial = metrics::FittsTrial {
+                    distance: length,
+                    width: width,
+                    deviation: deviation,
+                    time: self.selection_timer.elapsed().as_millis(),
+                    hit: hit,
+                };
+                self.trials.push(trial);
+                self.selections.push(metrics::FittsSelection { 
+                    trial: trial, 
+                    from: from, 
+                    target: target, 
+                    endpoint: self.cursor,
+                });
+            }
+
+            self.index = self.index + 1;
+            self.selection_timer = Instant::now();
+            if self.index < self.positions.len() {
+                let next = self.positions[self.index];
+                self.view = Some(msg::target2D(self.cursor.0, self.cursor.1, next.0, next.1, width));
+            }
+            else {
+                // condition done, move on to next
+                self.end_condition();
+                self.condition = self.condition + 1;
+                if self.condition == self.slide.conditions.len() {
+                    return Some(Outcome::Completed);
+                }
+                self.start_condition();
+            }
+        }
+        else {
+            self.cursor = self.slide.cursor(x, y);
+            let target = self.positions[self.index];
+            self.view = Some(msg::target2D(self.cursor.0, self.cursor.1, target.0, target.1, width));
+        }
+        None
+    }
+
+    fn tick(&mut self, _dt: f32, outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        if self.overall_timer.elapsed().as_secs() >= self.slide.duration {
+            return Some(Outcome::Completed);
+        }
+
+        let selections = self.selections.len();
+        let total = self.slide.conditions.len() * self.slide.selections as usize;
+        let misses = self.selections.iter().filter(|s| !s.trial.hit).count();
+        let condition = self.condition + 1;
+        let conditions = self.slide.conditions.len();
+        self.live.update(outbound_msg, || vec![
+            ("condition".to_string(), format!("{} of {}", condition, conditions)),
+            ("selections".to_string(), format!("{} of {}", selections, total)),
+            ("misses".to_string(), misses.to_string())]);
+        None
+    }
+
+    fn render(&mut self) -> Option<msg::Message> {
+        self.view.take()
+    }
+
+    fn paused(&mut self, duration: Duration) {
+        self.overall_timer = self.overall_timer + duration;
+        self.selection_timer = self.selection_timer + duration;
+    }
+}
+
+impl Slide for Fitts {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // jump to 2D target page
+        outbound_msg.send(msg::materialIndex(self.material, msg::target2d_num()));
+        outbound_msg.send(msg::gotoTarget2D());
+
+        let mut fitts = FittsRun {
+            slide: self,
+            overall_timer: Instant::now(),
+            cursor: (Fitts::CENTRE_X, Fitts::CENTRE_Y),
+            selections: vec![],
+            summaries: vec![],
+            condition: 0,
+            positions: vec![],
+            trials: vec![],
+            index: 0,
+            selection_timer: Instant::now(),
+            view: None,
+            live: runtime::LiveStats::new(),
+        };
+
+        let mut outcome = Outcome::Completed;
+        if !self.conditions.is_empty() {
+            fitts.start_condition();
+            let mut clock = events.clock();
+            outcome = runtime::run(&mut fitts, events, outbound_msg, deadline, Some(&mut clock));
+            world.writeFrameTiming("fitts".to_string(), self.material, &clock);
+
+            // condition in progress when slide finished
+            if fitts.condition < self.conditions.len() {
+                fitts.end_condition();
+            }
+        }
+
+        world.writeFitts(self.material, &fitts.selections, &fitts.summaries);
+        outcome
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Hold gesture
+//
+// Similar to press, but rather than moving on once the ring is reached, the 
+// user must hold pressure within a band for a fixed period, so that its 
+// steadiness can be measured.
+//-----------------------------------------------------------------------------
+
+/// Hold page of survey presentation
+#[derive(Deserialize)]
+pub struct Hold {
+    /// material index
+    material: u32,
+    /// maximum duration of test
+    duration: u64,
+    /// time (seconds) pressure should be held for, may be less than a second
+    hold: f32,
+    /// lower bound of target pressure band
+    lower: f32,
+    /// upper bound of target pressure band
+    upper: f32,
+}
+
+impl Hold {
+    pub fn new(material: u32, duration: u64, hold: f32, lower: f32, upper: f32) -> Self {
+        Hold {
+            material: material,
+            duration: duration,
+            hold: hold,
+            lower: lower,
+            upper: upper,
+        }
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if !(self.lower < self.upper) {
+            return Err("hold lower pressure must be less than upper".to_string());
+        }
+        if !(self.hold > 0.0 && self.hold <= self.duration as f32) {
+            return Err("hold time must be greater than 0 and no more than duration".to_string());
+        }
+        Ok(())
+    }
+
+    /// map pressure onto radius of circle
+    fn radius(pressure: f32) -> f32 {
+        range(Press::OUTPUT_START, Press::OUTPUT_END, Press::INPUT_START, Press::INPUT_END, pressure)
+    }
+}
+
+/// State of a running hold slide
+struct HoldTrial<'a> {
+    slide: &'a Hold,
+    band_inner: f32,
+    band_outer: f32,
+    overall_timer: Instant,
+    // full trace of contacts, and (time, pressure) within hold window
+    data: Vec<world::Contacts>,
+    window: Vec<(f32, f32)>,
+    // hold window begins when pressure enters band, and is reset if it leaves
+    hold_timer: Option<Instant>,
+    // view to be rendered at next frame
+    view: Option<msg::Message>,
+    live: runtime::LiveStats,
+}
+
+impl<'a> HoldTrial<'a> {
+    /// has pressure been held for long enough
+    fn held(&self) -> Option<Outcome> {
+        match self.hold_timer {
+            Some(timer) if timer.elapsed().as_millis() as f32 >= self.slide.hold * 1000.0 => Some(Outcome::Completed),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> runtime::Handler for HoldTrial<'a> {
+    fn input(&mut self, input: msg::SenselMessage, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (input_type, pressure, x, y, material, timestamp) = input;
+        if material == self.slide.material {
+            // lifting ends a hold, but is not a pressure sample
+            if input_type == msg::InputType::End {
+                self.hold_timer = None;
+                self.window.clear();
+                self.view = Some(msg::hold(Hold::radius(0.0), self.band_inner, self.band_outer, 0.0));
+                return None;
+            }
+
+            self.data[0].push((self.overall_timer.elapsed().as_millis(), pressure, x, y, timestamp));
+
+            if pressure >= self.slide.lower && pressure <= self.slide.upper {
+                if self.hold_timer.is_none() {
+                    self.hold_timer = Some(Instant::now());
+                }
+            }
+            else {
+                // left band, so hold must start again
+                self.hold_timer = None;
+                self.window.clear();
+            }
+
+            let progress = match self.hold_timer {
+                Some(timer) => {
+                    self.window.push((timer.elapsed().as_secs_f32(), pressure));
+                    f32::min(timer.elapsed().as_secs_f32() / self.slide.hold, 1.0)
+                },
+                None => 0.0,
+            };
+
+            self.view = Some(msg::hold(Hold::radius(pressure), self.band_inner, self.band_outer, progress));
+        }
+        self.held()
+    }
+
+    fn tick(&mut self, _dt: f32, outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        if self.overall_timer.elapsed().as_secs() >= self.slide.duration {
+            return Some(Outcome::Completed);
+        }
+
+        let held = self.hold_timer.map_or(0.0, |timer| timer.elapsed().as_secs_f32());
+        let hold = self.slide.hold;
+        self.live.update(outbound_msg, || vec![("held".to_string(), format!("{:.1}s of {:.1}s", held, hold))]);
+        self.held()
+    }
+
+    fn render(&mut self) -> Option<msg::Message> {
+        self.view.take()
+    }
+
+    fn paused(&mut self, duration: Duration) {
+        self.overall_timer = self.overall_timer + duration;
+        self.hold_timer = self.hold_timer.map(|timer| timer + duration);
+    }
+}
+
+impl Slide for Hold {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // jump to hold page
+        outbound_msg.send(msg::materialIndex(self.material, msg::hold_num()));
+        outbound_msg.send(msg::gotoHold());
+
+        let band_inner = Hold::radius(self.lower);
+        let band_outer = Hold::radius(self.upper);
+        outbound_msg.send(msg::hold(Press::OUTPUT_START, band_inner, band_outer, 0.0));
+
+        let mut trial = HoldTrial {
+            slide: self,
+            band_inner: band_inner,
+            band_outer: band_outer,
+            overall_timer: Instant::now(),
+            data: vec![vec![]],
+            window: vec![],
+            hold_timer: None,
+            view: None,
+            live: runtime::LiveStats::new(),
+        };
+
+        let mut clock = events.clock();
+        let outcome = runtime::run(&mut trial, events, outbound_msg, deadline, Some(&mut clock));
+        world.writeFrameTiming("hold".to_string(), self.material, &clock);
+
+        // window is partial if time ran out before pressure was held for long enough
+        let completed = trial.held().is_some();
+        let steadiness = metrics::Steadiness::new((self.lower + self.upper) / 2.0, &trial.window);
+        world.writeGesture("hold".to_string(), self.material, vec![(self.lower, self.upper)], trial.data);
+        world.writeSteadiness("hold".to_string(), self.material, (self.lower + self.upper) / 2.0, completed, &steadiness);
+        outcome
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Pressure tracking
+//
+// The press animation is reused, but now the ring follows a time varying 
+// target and the user modulates pressure to keep the circle on it.
+//-----------------------------------------------------------------------------
+
+/// Shape of ring radius over time
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PressTarget {
+    /// ramp up and back down again, over period seconds
+    Ramp { period: f32 },
+    /// sine wave, with period seconds
+    Sine { period: f32 },
+    /// smooth random curve, through a new random point every interval seconds
+    Random { interval: f32 },
+}
+
+/// Pressure tracking page of survey presentation
+#[derive(Deserialize)]
+pub struct PressTrack {
+    /// material index
+    material: u32,
+    /// duration to test
+    duration: u64,
+    /// circle radius within tolerance of ring radius is on target
+    tolerance: f32,
+    /// shape of target
+    target: PressTarget,
+}
+
+impl PressTrack {
+    pub fn new(material: u32, duration: u64, tolerance: f32, target: PressTarget) -> Self {
+        PressTrack {
+            material: material,
+            duration: duration,
+            tolerance: tolerance,
+            target: target,
+        }
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.duration == 0 {
+            return Err("press track duration must be greater than 0".to_string());
+        }
+        if !(self.tolerance >= 0.0) {
+            return Err("press track tolerance must not be negative".to_string());
+        }
+        match self.target {
+            PressTarget::Ramp { period } | PressTarget::Sine { period } if !(period > 0.0) => {
+                Err("press track period must be greater than 0".to_string())
+            },
+            PressTarget::Random { interval } if !(interval > 0.0) => {
+                Err("press track interval must be greater than 0".to_string())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// ring radius at time t (seconds), points are the random curve's control points
+    fn ring_radius(&self, t: f32, points: &Vec<f32>) -> f32 {
+        let min = Press::OUTPUT_RING_MIN;
+        let max = Press::OUTPUT_END;
+
+        // position of target between 0 and 1
+        let position = match self.target {
+            PressTarget::Ramp { period } => {
+                let phase = (t / period).fract();
+                if phase < 0.5 { phase * 2.0 } else { 2.0 - phase * 2.0 }
+            },
+            PressTarget::Sine { period } => {
+                0.5 - 0.5 * (2.0 * std::f32::consts::PI * t / period).cos()
+            },
+            PressTarget::Random { interval } => {
+                // cosine interpolation between control points
+                let index = (t / interval) as usize;
+                let from = points[usize::min(index, points.len() - 1)];
+                let to = points[usize::min(index + 1, points.len() - 1)];
+                let mu = (1.0 - (std::f32::consts::PI * (t / interval).fract()).cos()) / 2.0;
+                from * (1.0 - mu) + to * mu
+            },
+        };
+
+        min + position * (max - min)
+    }
+}
+
+/// State of a running pressure tracking slide
+struct PressTrackTrial<'a> {
+    slide: &'a PressTrack,
+    // control points for random target
+    points: Vec<f32>,
+    circle_radius: f32,
+    ring_radius: f32,
+    overall_timer: Instant,
+    data: Vec<world::Contacts>,
+    // (timestamp, ring radius, circle radius), per frame
+    frames: Vec<(u128, f32, f32)>,
+    // tracking error, only measured once user has touched
+    tracking: metrics::Tracking,
+    touched: bool,
+}
+
+impl<'a> runtime::Handler for PressTrackTrial<'a> {
+    fn input(&mut self, input: msg::SenselMessage, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (_input_type, pressure, x, y, material, timestamp) = input;
+        if material == self.slide.material {
+            self.circle_radius = range(
+                Press::OUTPUT_START,
+                Press::OUTPUT_END,
+                Press::INPUT_START,
+                Press::INPUT_END,
+                pressure);
+
+            self.data[0].push((self.overall_timer.elapsed().as_millis(), pressure, x, y, timestamp));
+            self.touched = true;
+        }
+        None
+    }
+
+    fn tick(&mut self, dt: f32, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        if self.overall_timer.elapsed().as_secs() >= self.slide.duration {
+            return Some(Outcome::Completed);
+        }
+
+        // update ring
+        self.ring_radius = self.slide.ring_radius(self.overall_timer.elapsed().as_secs_f32(), &self.points);
+
+        if self.touched {
+            let on_target = (self.ring_radius - self.circle_radius).abs() <= self.slide.tolerance;
+            self.tracking.update(self.circle_radius, self.ring_radius, on_target, dt);
+        }
+        self.frames.push((self.overall_timer.elapsed().as_millis(), self.ring_radius, self.circle_radius));
+        None
+    }
+
+    fn render(&mut self) -> Option<msg::Message> {
+        Some(msg::press(self.circle_radius, self.ring_radius))
+    }
+
+    fn paused(&mut self, duration: Duration) {
+        self.overall_timer = self.overall_timer + duration;
+    }
+}
+
+impl Slide for PressTrack {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // jump to press page
+        outbound_msg.send(msg::materialIndex(self.material, msg::press_num()));
+        outbound_msg.send(msg::gotoPress());
+
+        // control points for random target, starting at minimum so all targets begin in same place
+        let mut rng = rand::thread_rng();
+        let points: Vec<f32> = match self.target {
+            PressTarget::Random { interval } => {
+                let n = (self.duration as f32 / interval) as usize + 2;
+                (0..n).map(|i| if i == 0 { 0.0 } else { rng.gen_range(0.0, 1.0) }).collect()
+            },
+            _ => vec![],
+        };
+
+        let circle_radius = Press::OUTPUT_START;
+        let initial_ring_radius = self.ring_radius(0.0, &points);
+        outbound_msg.send(msg::press(circle_radius, initial_ring_radius));
+
+        let mut trial = PressTrackTrial {
+            slide: self,
+            points: points,
+            circle_radius: circle_radius,
+            ring_radius: initial_ring_radius,
+            overall_timer: Instant::now(),
+            data: vec![vec![]],
+            frames: vec![],
+            tracking: metrics::Tracking::new(),
+            touched: false,
+        };
+
+        let mut clock = events.clock();
+        let outcome = runtime::run(&mut trial, events, outbound_msg, deadline, Some(&mut clock));
+
+        world.writeGesture("press_track".to_string(), self.material, vec![(Press::OUTPUT_START, initial_ring_radius)], trial.data);
+        world.writeFrames("press_track".to_string(), self.material, &trial.frames);
+        world.writeTracking("press_track".to_string(), self.material, &trial.tracking);
+        world.writeFrameTiming("press_track".to_string(), self.material, &clock);
+        outcome
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Case when users reports properties about one or more materials
+//-----------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct Response {
+    // name of property to requested for material(s)
+    name: String,
+    // number of materials to be responded on
+    num_materials: u32,
+    // number of slide on client slide (required as there can be different response pages)
+    slide_num: u32,
+}
 
 impl Response {
     pub fn new(name: String, num_materials: u32, slide_num: u32) -> Self {
@@ -598,52 +1706,1084 @@ impl Response {
             slide_num: slide_num,
         }
     }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.num_materials == 0 {
+            return Err("response needs at least 1 material".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// State of a running response slide
+struct ResponseTrial {
+    num_materials: usize,
+    // when more than one material is requested the user is ranking them, so each 
+    // material can only be given once
+    ranking: bool,
+    // material touch started on, a response requires touch to end on the same material
+    start_material: Option<u32>,
+    // responses for page reported as single CSV entry
+    materials: Vec<u32>,
+}
+
+impl runtime::Handler for ResponseTrial {
+    fn input(&mut self, input: msg::SenselMessage, outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (input_type, _pressure, _x, _y, material, _timestamp) = input;
+
+        // track initial touch
+        if input_type == msg::InputType::Start {
+            self.start_material = Some(material);
+        }
+        else if input_type == msg::InputType::End && self.start_material.is_some() { 
+            if self.start_material == Some(material) {
+                // on release record material as response
+                if self.materials.len() == self.num_materials {
+                    outbound_msg.send(msg::responseRejected(
+                        material, "all materials selected, undo to change".to_string()));
+                }
+                else if self.ranking && self.materials.contains(&material) {
+                    outbound_msg.send(msg::responseRejected(
+                        material, "material already selected".to_string()));
+                }
+                else {
+                    self.materials.push(material);
+                    outbound_msg.send(msg::responseSelection(&self.materials, self.num_materials as u32));
+                }
+            }
+            self.start_material = None;
+        }
+        None
+    }
+
+    fn message(&mut self, data: msg::Message, outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        // undo or confirm selection
+        if msg::is_response_undo(data.clone()) {
+            self.materials.pop();
+            outbound_msg.send(msg::responseSelection(&self.materials, self.num_materials as u32));
+        }
+        else if msg::is_response_confirm(data) {
+            if self.materials.len() == self.num_materials {
+                return Some(Outcome::Completed);
+            }
+            outbound_msg.send(msg::responseSelection(&self.materials, self.num_materials as u32));
+        }
+        None
+    }
 }
 
 impl Slide for Response {
     fn run(&self, 
         world: &mut world::World,
-        inbound_osc: &Receiver<msg::SenselMessage>,
-        outbound_msg: &ws_server::WSServer, 
-        inbound_msg:  &Receiver<msg::Message>) {
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
 
-        // empty osc channel, in case of any after touches
-        loop {
-            match inbound_osc.try_recv() {
-                Ok(_) => {
-                },
-                _ => { 
-                    break;
+        // empty osc input, in case of any after touches
+        events.flush_input();
+
+        // goto slide
+        outbound_msg.send(msg::gotoSlide(self.slide_num));
+
+        let num_materials = self.num_materials as usize;
+        let mut trial = ResponseTrial {
+            num_materials: num_materials,
+            ranking: num_materials > 1,
+            start_material: None,
+            materials: Vec::with_capacity(num_materials),
+        };
+        outbound_msg.send(msg::responseSelection(&trial.materials, num_materials as u32));
+
+        let outcome = runtime::run(&mut trial, events, outbound_msg, deadline, None);
+        if outcome == Outcome::Completed {
+            // write out response(s) to CSV
+            world.writeResponse(self.name.clone(), trial.materials.iter().map(|m| m.to_string()).collect());
+        }
+        outcome
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Pairwise comparison
+//
+// Two materials are presented per trial and the user touches the one that 
+// better fits an attribute (two alternative forced choice). Every pair is 
+// presented, with sides counterbalanced, in a random order.
+//-----------------------------------------------------------------------------
+
+/// Pairwise comparison page of survey presentation
+#[derive(Deserialize)]
+pub struct Pairwise {
+    /// attribute materials are compared on, e.g. "responsive"
+    attribute: String,
+    /// materials to be compared
+    materials: Vec<u32>,
+    /// number of times each pair is presented
+    #[serde(default = "Pairwise::default_repeats")]
+    repeats: u32,
+}
+
+impl Pairwise {
+    pub fn new(attribute: String, materials: Vec<u32>, repeats: u32) -> Self {
+        Pairwise {
+            attribute: attribute,
+            materials: materials,
+            repeats: repeats,
+        }
+    }
+
+    fn default_repeats() -> u32 {
+        1
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.materials.len() < 2 {
+            return Err("pairwise slide needs at least 2 materials".to_string());
+        }
+        if self.repeats < 1 {
+            return Err("pairwise slide needs at least 1 repeat".to_string());
+        }
+        Ok(())
+    }
+
+    /// balanced schedule of (left, right) pairs. Each pair is presented repeats times, 
+    /// on alternating sides, and the same pair is not presented twice in a row (when 
+    /// there is more than one pair).
+    fn schedule(&self) -> Vec<(u32, u32)> {
+        let mut rng = rand::thread_rng();
+
+        let mut pairs = vec![];
+        for i in 0..self.materials.len() {
+            for j in i+1..self.materials.len() {
+                // random side for first presentation, then alternate
+                let swap: bool = rng.gen();
+                let (a, b) = (self.materials[i], self.materials[j]);
+                pairs.push((0..self.repeats)
+                    .map(|r| if swap == (r % 2 == 0) { (b, a) } else { (a, b) })
+                    .collect::<Vec<(u32, u32)>>());
+            }
+        }
+
+        // presentations left of each pair can be ordered without presenting the same 
+        // pair twice in a row, if the pair presented previously is not presented first
+        let possible = |left: &Vec<usize>, previous: usize| {
+            let total: usize = left.iter().sum();
+            left.iter().enumerate().all(|(k, n)| *n <= if k == previous { total / 2 } else { (total + 1) / 2 })
+        };
+
+        let mut left = vec![self.repeats as usize; pairs.len()];
+        let mut previous = pairs.len();
+        let mut schedule = vec![];
+        for _ in 0..pairs.len() * self.repeats as usize {
+            // next pair is one that keeps the rest of the schedule possible, or any 
+            // pair when that cannot be done (i.e. only one pair)
+            let keeps_possible = |k: usize| {
+                let mut after = left.clone();
+                after[k] = after[k] - 1;
+                k != previous && possible(&after, k)
+            };
+            let mut candidates: Vec<usize> = (0..pairs.len()).filter(|&k| left[k] > 0 && keeps_possible(k)).collect();
+            if candidates.is_empty() {
+                candidates = (0..pairs.len()).filter(|&k| left[k] > 0).collect();
+            }
+
+            // weighted by presentations left, so every order is possible
+            let k = *candidates.choose_weighted(&mut rng, |&k| left[k]).unwrap();
+            schedule.push(pairs[k][self.repeats as usize - left[k]]);
+            left[k] = left[k] - 1;
+            previous = k;
+        }
+
+        schedule
+    }
+}
+
+/// State of a running pairwise slide
+struct PairwiseTrials<'a> {
+    slide: &'a Pairwise,
+    schedule: Vec<(u32, u32)>,
+    // current trial and time it was presented
+    trial: usize,
+    timer: Instant,
+    // material touch started on, a choice requires touch to end on the same material
+    start_material: Option<u32>,
+    // (trial, left, right, choice, time) of each completed trial
+    choices: Vec<(u32, u32, u32, u32, u128)>,
+}
+
+impl<'a> PairwiseTrials<'a> {
+    /// present current trial
+    fn present(&mut self, outbound_msg: &ws_server::WSServer) {
+        let (left, right) = self.schedule[self.trial];
+        outbound_msg.send(msg::pairwise(
+            self.slide.attribute.clone(), left, right, self.trial as u32 + 1, self.schedule.len() as u32));
+        self.timer = Instant::now();
+        self.start_material = None;
+    }
+}
+
+impl<'a> runtime::Handler for PairwiseTrials<'a> {
+    fn input(&mut self, input: msg::SenselMessage, outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (input_type, _pressure, _x, _y, material, _timestamp) = input;
+        let (left, right) = self.schedule[self.trial];
+
+        if input_type == msg::InputType::Start {
+            self.start_material = Some(material);
+        }
+        else if input_type == msg::InputType::End {
+            if self.start_material == Some(material) && (material == left || material == right) {
+                self.choices.push((self.trial as u32 + 1, left, right, material, self.timer.elapsed().as_millis()));
+                self.trial = self.trial + 1;
+                if self.trial == self.schedule.len() {
+                    return Some(Outcome::Completed);
                 }
+                self.present(outbound_msg);
+                return None;
             }
+            self.start_material = None;
         }
+        None
+    }
+
+    fn paused(&mut self, duration: Duration) {
+        self.timer = self.timer + duration;
+    }
+}
+
+impl Slide for Pairwise {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // empty osc input, in case of any after touches
+        events.flush_input();
+
+        let mut trials = PairwiseTrials {
+            slide: self,
+            schedule: self.schedule(),
+            trial: 0,
+            timer: Instant::now(),
+            start_material: None,
+            choices: vec![],
+        };
 
         // goto slide
-        outbound_msg.send(msg::gotoSlide(self.slide_num));
+        outbound_msg.send(msg::gotoPairwise());
+        trials.present(outbound_msg);
 
-        let mut num_materials = self.num_materials;
-        let mut start_happened = false;
+        let outcome = runtime::run(&mut trials, events, outbound_msg, deadline, None);
 
-        // responses for page reported as single CSV entry
-        let mut materials: Vec<String> = Vec::with_capacity(num_materials as usize);
+        for (trial, left, right, choice, time) in trials.choices {
+            world.writePairwise(&self.attribute, trial, left, right, choice, time);
+        }
+        outcome
+    }
+}
 
-        while num_materials > 0 {
-            match inbound_osc.recv() {
-                Ok((input_type, pressure, x, y, material)) => {
-                    // track initial touch
-                    if input_type == msg::InputType::Start {
-                        start_happened = true;
+//-----------------------------------------------------------------------------
+// Forms
+//
+// Questionnaires, e.g. demographics, are a set of fields sent to the client as 
+// a schema. Answers are validated here and stored in the participant metadata.
+//-----------------------------------------------------------------------------
+
+/// Kind of a form field, along with its constraints
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldKind {
+    Text { 
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_length: Option<usize>,
+    },
+    Number {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+        #[serde(default)]
+        integer: bool,
+    },
+    Single { options: Vec<String> },
+    Multi { options: Vec<String> },
+}
+
+/// A single form field, name is reported back by client and label is shown to user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FormField {
+    pub name: String,
+    pub label: String,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl FormField {
+    /// validate answer for field, returning value to be stored
+    fn validate(&self, value: Option<&Value>) -> Result<Value, String> {
+        let value = match value {
+            None | Some(Value::Null) => {
+                return if self.required { Err("required".to_string()) } else { Ok(Value::Null) };
+            },
+            Some(Value::String(s)) if s.trim().is_empty() => {
+                return if self.required { Err("required".to_string()) } else { Ok(Value::Null) };
+            },
+            Some(v) => v,
+        };
+
+        match &self.kind {
+            FieldKind::Text { max_length } => {
+                let s = value.as_str().ok_or("expected text".to_string())?;
+                match max_length {
+                    Some(max) if s.chars().count() > *max => Err(format!("at most {} characters", max)),
+                    _ => Ok(json!(s.trim())),
+                }
+            },
+            FieldKind::Number { min, max, integer } => {
+                // numbers may arrive as strings from form inputs
+                let n = match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => s.trim().parse::<f64>().ok(),
+                    _ => None,
+                }.ok_or("expected a number".to_string())?;
+
+                if *integer && n.fract() != 0.0 {
+                    return Err("expected a whole number".to_string());
+                }
+                if let Some(min) = min {
+                    if n < *min {
+                        return Err(format!("must be at least {}", min));
+                    }
+                }
+                if let Some(max) = max {
+                    if n > *max {
+                        return Err(format!("must be at most {}", max));
                     }
-                    else if input_type == msg::InputType::End && start_happened { 
-                        // on release record material as response
-                        materials.push(material.to_string());
-                        num_materials = num_materials - 1;
+                }
+                Ok(json!(n))
+            },
+            FieldKind::Single { options } => {
+                let s = value.as_str().ok_or("expected a single choice".to_string())?;
+                if options.iter().any(|o| o == s) { Ok(json!(s)) } else { Err(format!("'{}' is not an option", s)) }
+            },
+            FieldKind::Multi { options } => {
+                let choices = value.as_array().ok_or("expected a list of choices".to_string())?;
+                let mut selected = vec![];
+                for c in choices {
+                    let s = c.as_str().ok_or("expected a list of choices".to_string())?;
+                    if !options.iter().any(|o| o == s) {
+                        return Err(format!("'{}' is not an option", s));
                     }
+                    selected.push(s.to_string());
+                }
+                if self.required && selected.is_empty() {
+                    return Err("required".to_string());
+                }
+                Ok(json!(selected))
+            },
+        }
+    }
+}
+
+/// Form page of survey presentation
+#[derive(Deserialize)]
+pub struct Form {
+    /// name answers are stored under in participant metadata
+    name: String,
+    /// title shown to user
+    title: String,
+    /// fields of form
+    fields: Vec<FormField>,
+}
+
+impl Form {
+    pub fn new(name: String, title: String, fields: Vec<FormField>) -> Self {
+        Form {
+            name: name,
+            title: title,
+            fields: fields,
+        }
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.fields.is_empty() {
+            return Err("form slide has no fields".to_string());
+        }
+        for (i, field) in self.fields.iter().enumerate() {
+            if self.fields[..i].iter().any(|f| f.name == field.name) {
+                return Err(format!("form field '{}' defined more than once", field.name));
+            }
+            match &field.kind {
+                FieldKind::Single { options } | FieldKind::Multi { options } if options.is_empty() => {
+                    return Err(format!("form field '{}' has no options", field.name));
                 },
                 _ => {},
             }
         }
-        // write out response(s) to CSV
-        world.writeResponse(self.name.clone(), materials);
+        Ok(())
+    }
+
+    /// validate all answers, returning either values to be stored or error for each invalid field
+    fn validate(&self, values: &Map<String, Value>) -> Result<Map<String, Value>, Map<String, Value>> {
+        let mut valid = Map::new();
+        let mut errors = Map::new();
+
+        for field in &self.fields {
+            match field.validate(values.get(&field.name)) {
+                Ok(v) => { valid.insert(field.name.clone(), v); },
+                Err(e) => { errors.insert(field.name.clone(), json!(e)); },
+            }
+        }
+        for name in values.keys() {
+            if !self.fields.iter().any(|f| &f.name == name) {
+                errors.insert(name.clone(), json!("unknown field"));
+            }
+        }
+
+        if errors.is_empty() { Ok(valid) } else { Err(errors) }
+    }
+}
+
+impl Slide for Form {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // send form schema and jump to form page
+        outbound_msg.send(msg::formSchema(self.title.clone(), &self.fields));
+        outbound_msg.send(msg::gotoForm());
+
+        loop {
+            match wait_msg(events, deadline) {
+                Wait::Message(data) => {
+                    match msg::form(data) {
+                        Ok(values) => {
+                            match self.validate(&values) {
+                                Ok(valid) => {
+                                    world.writeMetadata(&self.name, Value::Object(valid));
+                                    return Outcome::Completed;
+                                },
+                                Err(errors) => {
+                                    outbound_msg.send(msg::formErrors(errors));
+                                }
+                            }
+                        },
+                        _ => {},
+                    }
+                },
+                Wait::Interrupted(outcome) => return outcome,
+            }
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// NASA-TLX
+//
+// Perceived workload, rated on six subscales from 0 to 100, using the Likert 
+// page as a visual analogue scale. Optionally the subscales are then weighted 
+// by choosing the greater contributor to workload from each pair of subscales.
+//-----------------------------------------------------------------------------
+
+/// NASA-TLX page of survey presentation
+#[derive(Deserialize)]
+pub struct Tlx {
+    /// material index
+    material: u32,
+    /// gesture material was used with
+    gesture: String,
+    /// present pairwise weighting of subscales, after ratings
+    #[serde(default)]
+    weighted: bool,
+}
+
+impl Tlx {
+    const MAX: u32 = 100;
+
+    pub fn new(material: u32, gesture: String, weighted: bool) -> Self {
+        Tlx {
+            material: material,
+            gesture: gesture,
+            weighted: weighted,
+        }
+    }
+
+    /// the six subscales, with their low and high anchors
+    fn subscales() -> Vec<LikertItem> {
+        [("mental", "Mental Demand: How mentally demanding was the task?", "Very Low", "Very High"),
+         ("physical", "Physical Demand: How physically demanding was the task?", "Very Low", "Very High"),
+         ("temporal", "Temporal Demand: How hurried or rushed was the pace of the task?", "Very Low", "Very High"),
+         ("performance", "Performance: How successful were you in accomplishing what you were asked to do?", "Perfect", "Failure"),
+         ("effort", "Effort: How hard did you have to work to accomplish your level of performance?", "Very Low", "Very High"),
+         ("frustration", "Frustration: How insecure, discouraged, irritated, stressed, and annoyed were you?", "Very Low", "Very High")]
+            .iter()
+            .map(|(name, text, left, right)| LikertItem {
+                name: name.to_string(),
+                text: text.to_string(),
+                left: Some(left.to_string()),
+                right: Some(right.to_string()),
+            })
+            .collect()
+    }
+
+    /// present each pair of subscales in random order, returning number of times each 
+    /// subscale was chosen, or the outcome if slide was interrupted
+    fn weights(
+        subscales: &Vec<LikertItem>,
+        outbound_msg: &ws_server::WSServer,
+        events: &runtime::Events,
+        deadline: Option<Instant>) -> Result<Vec<u32>, Outcome> {
+
+        let mut rng = rand::thread_rng();
+        let mut pairs = vec![];
+        for i in 0..subscales.len() {
+            for j in i+1..subscales.len() {
+                pairs.push(if rng.gen() { (i, j) } else { (j, i) });
+            }
+        }
+        pairs.shuffle(&mut rng);
+
+        outbound_msg.send(msg::gotoTlxWeights());
+
+        let mut weights = vec![0; subscales.len()];
+        for (trial, &(left, right)) in pairs.iter().enumerate() {
+            outbound_msg.send(msg::tlxPair(
+                &subscales[left], &subscales[right], trial as u32 + 1, pairs.len() as u32));
+
+            loop {
+                match wait_msg(events, deadline) {
+                    Wait::Message(data) => {
+                        match msg::tlxChoice(data) {
+                            Ok(choice) if choice == subscales[left].name => {
+                                weights[left] = weights[left] + 1;
+                                break;
+                            },
+                            Ok(choice) if choice == subscales[right].name => {
+                                weights[right] = weights[right] + 1;
+                                break;
+                            },
+                            Ok(choice) => {
+                                error!("tlx choice '{}' is not one of presented pair", choice);
+                            },
+                            _ => {},
+                        }
+                    },
+                    Wait::Interrupted(outcome) => {
+                        return Err(outcome);
+                    },
+                }
+            }
+        }
+
+        Ok(weights)
+    }
+}
+
+impl Slide for Tlx {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        let subscales = Tlx::subscales();
+
+        let answers = match collect_ratings(
+            self.material,
+            &self.gesture,
+            msg::likertDefinition("vas", &subscales, Tlx::MAX, &vec![]),
+            &subscales,
+            &|l| validate_rating(&subscales, 0, Tlx::MAX, l),
+            outbound_msg,
+            events,
+            deadline) {
+            Ok(answers) => answers,
+            Err(outcome) => return outcome,
+        };
+
+        let ratings: Vec<u32> = subscales.iter().map(|s| answers[&s.name].0.value).collect();
+
+        // ratings are kept even if weighting is interrupted, as raw TLX
+        let (weights, outcome) = if self.weighted {
+            match Tlx::weights(&subscales, outbound_msg, events, deadline) {
+                Ok(weights) => (Some(weights), Outcome::Completed),
+                Err(outcome) => (None, outcome),
+            }
+        }
+        else {
+            (None, Outcome::Completed)
+        };
+
+        world.writeTlx(&self.gesture, self.material, &ratings, weights.as_ref());
+        outcome
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Free text
+//-----------------------------------------------------------------------------
+
+/// Free text page of survey presentation, e.g. for comments about materials
+#[derive(Deserialize)]
+pub struct FreeText {
+    /// identifier of slide, response is stored under
+    name: String,
+    /// prompt shown to user
+    prompt: String,
+    /// minimum length of response, in characters
+    #[serde(default)]
+    min_length: usize,
+    /// maximum length of response, in characters
+    #[serde(default)]
+    max_length: Option<usize>,
+}
+
+impl FreeText {
+    pub fn new(name: String, prompt: String, min_length: usize, max_length: Option<usize>) -> Self {
+        FreeText {
+            name: name,
+            prompt: prompt,
+            min_length: min_length,
+            max_length: max_length,
+        }
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        match self.max_length {
+            Some(max) if max < self.min_length => {
+                Err(format!("free text maximum length {} is less than minimum {}", max, self.min_length))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// check response is within length limits
+    fn validate(&self, text: &str) -> Result<(), String> {
+        let length = text.chars().count();
+        if length < self.min_length {
+            return Err(format!("please write at least {} characters", self.min_length));
+        }
+        match self.max_length {
+            Some(max) if length > max => Err(format!("please write at most {} characters", max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Slide for FreeText {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // set prompt and jump to free text page
+        outbound_msg.send(msg::textPrompt(self.prompt.clone(), self.min_length, self.max_length));
+        outbound_msg.send(msg::gotoText());
+
+        loop {
+            match wait_msg(events, deadline) {
+                Wait::Message(data) => {
+                    match msg::text(data) {
+                        Ok(text) => {
+                            let text = text.trim();
+                            match self.validate(text) {
+                                Ok(()) => {
+                                    world.writeResponse(self.name.clone(), vec![text.to_string()]);
+                                    return Outcome::Completed;
+                                },
+                                Err(e) => {
+                                    outbound_msg.send(msg::textInvalid(e));
+                                }
+                            }
+                        },
+                        _ => {},
+                    }
+                },
+                Wait::Interrupted(outcome) => return outcome,
+            }
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Closing matter
+//-----------------------------------------------------------------------------
+
+/// Closing page of survey presentation, shows debrief and marks session complete
+#[derive(Deserialize)]
+pub struct Closing {
+    /// debrief shown to user (HTML)
+    #[serde(default)]
+    debrief: String,
+    /// show summary of participant's results
+    #[serde(default)]
+    summary: bool,
+}
+
+impl Closing {
+    pub fn new(debrief: String, summary: bool) -> Self {
+        Closing {
+            debrief: debrief,
+            summary: summary,
+        }
+    }
+}
+
+impl Slide for Closing {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        let summary = if self.summary { world.summary.clone() } else { vec![] };
+        outbound_msg.send(msg::closing(self.debrief.clone(), &summary));
+        outbound_msg.send(msg::gotoClosing());
+
+        // backend exits after last slide, so give messages time to be delivered, 
+        // unless skipped or aborted, in which case session is not complete
+        let delivered = Instant::now() + Duration::from_millis(500);
+        let until = deadline.map_or(delivered, |deadline| std::cmp::min(deadline, delivered));
+        loop {
+            match wait_msg(events, Some(until)) {
+                Wait::Message(_) => {},
+                Wait::Interrupted(Outcome::TimedOut) => {
+                    world.writeComplete();
+                    return Outcome::Completed;
+                },
+                Wait::Interrupted(outcome) => return outcome,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{channel, Receiver};
+    use crate::runtime::Handler;
+
+    /// server for handlers to send to, on any free port, along with messages sent
+    fn server() -> (ws_server::WSServer, Receiver<msg::Message>) {
+        let (inbound, _) = channel();
+        let server = ws_server::WSServer::new("127.0.0.1:0".to_string(), inbound);
+        let sent = server.monitor().observe();
+        (server, sent)
+    }
+
+    /// types of messages sent to participant
+    fn sent_types(sent: &Receiver<msg::Message>) -> Vec<String> {
+        sent.try_iter()
+            .map(|data| data["message"]["type"].as_str().unwrap_or("").to_string())
+            .collect()
+    }
+
+    fn touch(input_type: msg::InputType, material: u32) -> msg::SenselMessage {
+        (input_type, 500.0, 50.0, 50.0, material, msg::Timestamp { sensor: None, received: 0.0 })
+    }
+
+    fn fitts(selections: u32, two_d: bool) -> Fitts {
+        Fitts::new(1, 60, vec![(200.0, 20.0)], selections, two_d, 0.0, 0.0, 100.0, 100.0)
+    }
+
+    fn steps(positions: &Vec<(f32, f32)>) -> Vec<f32> {
+        positions
+            .windows(2)
+            .map(|p| ((p[1].0 - p[0].0).powi(2) + (p[1].1 - p[0].1).powi(2)).sqrt())
+            .collect()
+    }
+
+    #[test]
+    fn fitts_positions_cross_circle() {
+        for selections in vec![9, 10] {
+            let positions = fitts(selections, true).positions(200.0);
+            assert_eq!(positions.len(), selections as usize + 1);
+
+            let targets = selections as usize | 1;
+            let chord = 200.0 * (std::f32::consts::PI / (2.0 * targets as f32)).cos();
+            for step in steps(&positions) {
+                assert!((step - chord).abs() < 1e-3, "step {} is not {}", step, chord);
+            }
+        }
+    }
+
+    #[test]
+    fn fitts_positions_reciprocal() {
+        let positions = fitts(4, false).positions(200.0);
+        assert_eq!(positions.len(), 5);
+        for step in steps(&positions) {
+            assert!((step - 200.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn fitts_check() {
+        assert!(fitts(10, true).check().is_ok());
+        assert!(fitts(0, true).check().is_err());
+        assert!(Fitts::new(1, 60, vec![], 10, true, 0.0, 0.0, 100.0, 100.0).check().is_err());
+        assert!(Fitts::new(1, 60, vec![(0.0, 20.0)], 10, true, 0.0, 0.0, 100.0, 100.0).check().is_err());
+
+        // targets must be on canvas, which is wider than it is high
+        assert!(Fitts::new(1, 60, vec![(340.0, 20.0)], 10, true, 0.0, 0.0, 100.0, 100.0).check().is_ok());
+        assert!(Fitts::new(1, 60, vec![(400.0, 20.0)], 10, true, 0.0, 0.0, 100.0, 100.0).check().is_err());
+        assert!(Fitts::new(1, 60, vec![(400.0, 20.0)], 10, false, 0.0, 0.0, 100.0, 100.0).check().is_ok());
+        assert!(Fitts::new(1, 60, vec![(700.0, 20.0)], 10, false, 0.0, 0.0, 100.0, 100.0).check().is_err());
+    }
+
+    #[test]
+    fn hold_check() {
+        assert!(Hold::new(1, 10, 0.5, 200.0, 400.0).check().is_ok());
+        assert!(Hold::new(1, 10, 0.5, 400.0, 400.0).check().is_err());
+        assert!(Hold::new(1, 10, 0.0, 200.0, 400.0).check().is_err());
+        assert!(Hold::new(1, 10, 11.0, 200.0, 400.0).check().is_err());
+    }
+
+    /// ring radius as position between 0 (smallest) and 1 (largest)
+    fn ring_position(target: PressTarget, t: f32, points: &Vec<f32>) -> f32 {
+        let radius = PressTrack::new(1, 10, 2.0, target).ring_radius(t, points);
+        (radius - Press::OUTPUT_RING_MIN) / (Press::OUTPUT_END - Press::OUTPUT_RING_MIN)
+    }
+
+    #[test]
+    fn press_track_ring_radius() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        for (t, position) in vec![(0.0, 0.0), (0.5, 0.5), (1.0, 1.0), (1.5, 0.5), (2.0, 0.0)] {
+            assert!(close(ring_position(PressTarget::Ramp { period: 2.0 }, t, &vec![]), position));
+            assert!(close(ring_position(PressTarget::Sine { period: 2.0 }, t, &vec![]), position));
+        }
+
+        let points = vec![0.0, 1.0, 0.5];
+        for (t, position) in vec![(0.0, 0.0), (0.5, 0.5), (1.0, 1.0), (1.5, 0.75), (2.0, 0.5), (10.0, 0.5)] {
+            assert!(close(ring_position(PressTarget::Random { interval: 1.0 }, t, &points), position));
+        }
+    }
+
+    fn response(num_materials: usize) -> ResponseTrial {
+        ResponseTrial {
+            num_materials: num_materials,
+            ranking: num_materials > 1,
+            start_material: None,
+            materials: vec![],
+        }
+    }
+
+    /// touch started and ended on materials
+    fn select(trial: &mut ResponseTrial, server: &ws_server::WSServer, start: u32, end: u32) {
+        trial.input(touch(msg::InputType::Start, start), server);
+        trial.input(touch(msg::InputType::End, end), server);
+    }
+
+    #[test]
+    fn response_selects_on_same_material() {
+        let (server, sent) = server();
+        let mut trial = response(2);
+
+        // touch must start and end on the same material
+        select(&mut trial, &server, 1, 2);
+        assert!(trial.materials.is_empty());
+        trial.input(touch(msg::InputType::End, 3), &server);
+        assert!(trial.materials.is_empty());
+        assert!(sent_types(&sent).is_empty());
+
+        select(&mut trial, &server, 3, 3);
+        assert_eq!(trial.materials, vec![3]);
+        assert_eq!(sent_types(&sent), vec!["responseSelection"]);
+    }
+
+    #[test]
+    fn response_ranking_rejects_duplicates() {
+        let (server, sent) = server();
+        let mut trial = response(2);
+        select(&mut trial, &server, 1, 1);
+        select(&mut trial, &server, 1, 1);
+        assert_eq!(trial.materials, vec![1]);
+        assert_eq!(sent_types(&sent), vec!["responseSelection", "responseRejected"]);
+
+        // once all materials are selected, selections are rejected until undone
+        let mut trial = response(1);
+        select(&mut trial, &server, 1, 1);
+        select(&mut trial, &server, 2, 2);
+        assert_eq!(trial.materials, vec![1]);
+        assert_eq!(sent_types(&sent), vec!["responseSelection", "responseRejected"]);
+    }
+
+    #[test]
+    fn response_undo_and_confirm() {
+        let (server, sent) = server();
+        let mut trial = response(2);
+        select(&mut trial, &server, 1, 1);
+
+        // incomplete selection is not confirmed
+        assert_eq!(trial.message(json!({"type": "responseConfirm"}), &server), None);
+
+        select(&mut trial, &server, 2, 2);
+        select(&mut trial, &server, 3, 3);
+        assert_eq!(trial.materials, vec![1, 2]);
+
+        trial.message(json!({"type": "responseUndo"}), &server);
+        select(&mut trial, &server, 3, 3);
+        assert_eq!(trial.materials, vec![1, 3]);
+        assert_eq!(sent_types(&sent).last().map(|t| t.as_str()), Some("responseSelection"));
+
+        assert_eq!(trial.message(json!({"type": "responseConfirm"}), &server), Some(Outcome::Completed));
+    }
+
+    #[test]
+    fn response_check() {
+        assert!(Response::new("best".to_string(), 1, 3).check().is_ok());
+        assert!(Response::new("best".to_string(), 0, 3).check().is_err());
+    }
+
+    #[test]
+    fn pairwise_schedule() {
+        let unordered = |p: (u32, u32)| (u32::min(p.0, p.1), u32::max(p.0, p.1));
+        for (materials, repeats) in vec![(vec![1, 2, 3], 1), (vec![1, 2, 3], 3), (vec![1, 2, 3, 4], 2), (vec![1, 2, 3, 4, 5], 4)] {
+            let pairwise = Pairwise::new("responsive".to_string(), materials.clone(), repeats);
+            // schedule is random, so check a number of them
+            for _ in 0..200 {
+                let schedule = pairwise.schedule();
+                assert_eq!(schedule.len(), materials.len() * (materials.len() - 1) / 2 * repeats as usize);
+
+                for i in 0..materials.len() {
+                    for j in i+1..materials.len() {
+                        let (a, b) = (materials[i], materials[j]);
+                        // each pair presented repeats times, with sides balanced
+                        let left = schedule.iter().filter(|&&p| p == (a, b)).count() as i32;
+                        let right = schedule.iter().filter(|&&p| p == (b, a)).count() as i32;
+                        assert_eq!(left + right, repeats as i32);
+                        assert!((left - right).abs() <= 1);
+                    }
+                }
+
+                // same pair is never presented twice in a row
+                for p in schedule.windows(2) {
+                    assert!(unordered(p[0]) != unordered(p[1]), "{:?} repeats a pair", schedule);
+                }
+            }
+        }
+
+        // single pair can only be repeated, on alternating sides
+        let schedule = Pairwise::new("responsive".to_string(), vec![1, 2], 3).schedule();
+        assert_eq!(schedule.len(), 3);
+        assert!(schedule[0] == schedule[2] && schedule[0] != schedule[1]);
+    }
+
+    fn field(name: &str, kind: FieldKind, required: bool) -> FormField {
+        FormField { name: name.to_string(), label: name.to_string(), kind: kind, required: required }
+    }
+
+    #[test]
+    fn form_field_required() {
+        let required = field("name", FieldKind::Text { max_length: None }, true);
+        assert!(required.validate(None).is_err());
+        assert!(required.validate(Some(&Value::Null)).is_err());
+        assert!(required.validate(Some(&json!("  "))).is_err());
+        assert_eq!(required.validate(Some(&json!(" Ada "))), Ok(json!("Ada")));
+
+        let optional = field("name", FieldKind::Text { max_length: Some(3) }, false);
+        assert_eq!(optional.validate(None), Ok(Value::Null));
+        assert_eq!(optional.validate(Some(&json!(""))), Ok(Value::Null));
+        assert!(optional.validate(Some(&json!("four"))).is_err());
+        assert!(optional.validate(Some(&json!(4))).is_err());
+    }
+
+    #[test]
+    fn form_field_number() {
+        let age = field("age", FieldKind::Number { min: Some(18.0), max: Some(99.0), integer: true }, true);
+        assert_eq!(age.validate(Some(&json!(30))), Ok(json!(30.0)));
+        assert_eq!(age.validate(Some(&json!(" 30 "))), Ok(json!(30.0)));
+        assert!(age.validate(Some(&json!(30.5))).is_err());
+        assert!(age.validate(Some(&json!(17))).is_err());
+        assert!(age.validate(Some(&json!(100))).is_err());
+        assert!(age.validate(Some(&json!("thirty"))).is_err());
+
+        let any = field("height", FieldKind::Number { min: None, max: None, integer: false }, true);
+        assert_eq!(any.validate(Some(&json!(1.75))), Ok(json!(1.75)));
+    }
+
+    #[test]
+    fn form_field_options() {
+        let options = vec!["left".to_string(), "right".to_string()];
+        let single = field("hand", FieldKind::Single { options: options.clone() }, true);
+        assert_eq!(single.validate(Some(&json!("left"))), Ok(json!("left")));
+        assert!(single.validate(Some(&json!("both"))).is_err());
+        assert!(single.validate(Some(&json!(["left"]))).is_err());
+
+        let multi = field("hands", FieldKind::Multi { options: options }, true);
+        assert_eq!(multi.validate(Some(&json!(["left", "right"]))), Ok(json!(["left", "right"])));
+        assert!(multi.validate(Some(&json!([]))).is_err());
+        assert!(multi.validate(Some(&json!(["left", "both"]))).is_err());
+        assert!(multi.validate(Some(&json!("left"))).is_err());
+    }
+
+    #[test]
+    fn form_validate() {
+        let form = Form::new("demographics".to_string(), "About you".to_string(), vec![
+            field("age", FieldKind::Number { min: Some(18.0), max: None, integer: true }, true),
+            field("comment", FieldKind::Text { max_length: None }, false)]);
+        assert!(form.check().is_ok());
+
+        let values = |v: Value| v.as_object().unwrap().clone();
+        let valid = form.validate(&values(json!({"age": 30}))).unwrap();
+        assert_eq!(Value::Object(valid), json!({"age": 30.0, "comment": null}));
+
+        // every invalid field, and any unknown field, is reported
+        let errors = form.validate(&values(json!({"age": 12, "shoe": 9}))).unwrap_err();
+        assert_eq!(errors.keys().cloned().collect::<Vec<String>>(), vec!["age", "shoe"]);
+
+        let twice = Form::new("f".to_string(), "f".to_string(), vec![
+            field("age", FieldKind::Text { max_length: None }, false),
+            field("age", FieldKind::Text { max_length: None }, false)]);
+        assert!(twice.check().is_err());
+        let no_options = Form::new("f".to_string(), "f".to_string(), vec![
+            field("hand", FieldKind::Single { options: vec![] }, false)]);
+        assert!(no_options.check().is_err());
+    }
+
+    #[test]
+    fn tlx_weights() {
+        let (server, sent) = server();
+        let (inbound, receiver) = channel();
+        let events = runtime::Events::new(receiver, 60.0, server.monitor());
+        let subscales = Tlx::subscales();
+
+        // participant always chooses the subscale listed first, after first making
+        // a choice that is not one of the pair
+        let names: Vec<String> = subscales.iter().map(|s| s.name.clone()).collect();
+        std::thread::spawn(move || {
+            for data in sent.iter() {
+                let data = &data["message"];
+                if data["type"] == "tlxPair" {
+                    let pair = vec![data["left"]["name"].as_str().unwrap(), data["right"]["name"].as_str().unwrap()];
+                    let choice = names.iter().find(|n| pair.contains(&n.as_str())).unwrap();
+                    inbound.send(msg::Event::Message(json!({"type": "tlxChoice", "choice": "none"}))).ok();
+                    inbound.send(msg::Event::Message(json!({"type": "tlxChoice", "choice": choice}))).ok();
+                }
+            }
+        });
+
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        let weights = Tlx::weights(&subscales, &server, &events, deadline).ok().unwrap();
+        assert_eq!(weights, vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(weights.iter().sum::<u32>(), 15);
+    }
+
+    #[test]
+    fn press_track_check() {
+        assert!(PressTrack::new(1, 10, 2.0, PressTarget::Sine { period: 2.0 }).check().is_ok());
+        assert!(PressTrack::new(1, 0, 2.0, PressTarget::Sine { period: 2.0 }).check().is_err());
+        assert!(PressTrack::new(1, 10, -1.0, PressTarget::Sine { period: 2.0 }).check().is_err());
+        assert!(PressTrack::new(1, 10, 2.0, PressTarget::Ramp { period: 0.0 }).check().is_err());
+        assert!(PressTrack::new(1, 10, 2.0, PressTarget::Sine { period: 0.0 }).check().is_err());
+        assert!(PressTrack::new(1, 10, 2.0, PressTarget::Random { interval: 0.0 }).check().is_err());
     }
 }
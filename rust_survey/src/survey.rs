@@ -0,0 +1,178 @@
+//! Survey definition, read from JSON
+//!
+//! Copyright: Benedict R. Gaster
+//!
+//!
+
+use std::fs::File;
+use std::io::Read;
+use std::time::Duration;
+
+use crate::slide;
+use crate::task;
+
+//-----------------------------------------------------------------------------
+
+/// A slide within the survey definition, the field "slide" gives the type of
+/// slide, all other fields are passed to the slide itself
+#[derive(Deserialize)]
+#[serde(tag = "slide", rename_all = "snake_case")]
+pub enum SlideDef {
+    FrontMatter(slide::FrontMatter),
+    Consent(slide::Consent),
+    Likert(slide::Likert),
+    Vas(slide::Vas),
+    SemanticDifferential(slide::SemanticDifferential),
+    Press(slide::Press),
+    Slider(slide::Slider),
+    Tap(slide::Tap),
+    Task(task::Task),
+    #[serde(rename = "target2d")]
+    Target2D(slide::Target2D),
+    Fitts(slide::Fitts),
+    Hold(slide::Hold),
+    PressTrack(slide::PressTrack),
+    Response(slide::Response),
+    Pairwise(slide::Pairwise),
+    Form(slide::Form),
+    Tlx(slide::Tlx),
+    FreeText(slide::FreeText),
+    Closing(slide::Closing),
+}
+
+impl SlideDef {
+    /// name of slide type, as given in survey definition
+    fn name(&self) -> &'static str {
+        match self {
+            SlideDef::FrontMatter(_) => "front_matter",
+            SlideDef::Consent(_) => "consent",
+            SlideDef::Likert(_) => "likert",
+            SlideDef::Vas(_) => "vas",
+            SlideDef::SemanticDifferential(_) => "semantic_differential",
+            SlideDef::Press(_) => "press",
+            SlideDef::Slider(_) => "slider",
+            SlideDef::Tap(_) => "tap",
+            SlideDef::Task(_) => "task",
+            SlideDef::Target2D(_) => "target2d",
+            SlideDef::Fitts(_) => "fitts",
+            SlideDef::Hold(_) => "hold",
+            SlideDef::PressTrack(_) => "press_track",
+            SlideDef::Response(_) => "response",
+            SlideDef::Pairwise(_) => "pairwise",
+            SlideDef::Form(_) => "form",
+            SlideDef::Tlx(_) => "tlx",
+            SlideDef::FreeText(_) => "free_text",
+            SlideDef::Closing(_) => "closing",
+        }
+    }
+
+    fn into_slide(self) -> Result<Box<dyn slide::Slide>, String> {
+        Ok(match self {
+            SlideDef::FrontMatter(s) => Box::new(s),
+            SlideDef::Consent(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Likert(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Vas(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::SemanticDifferential(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Press(s) => Box::new(s),
+            SlideDef::Slider(s) => Box::new(s),
+            SlideDef::Tap(s) => Box::new(s),
+            SlideDef::Task(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Target2D(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Fitts(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Hold(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::PressTrack(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Response(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Pairwise(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Form(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Tlx(s) => Box::new(s),
+            SlideDef::FreeText(s) => {
+                s.check()?;
+                Box::new(s)
+            },
+            SlideDef::Closing(s) => Box::new(s),
+        })
+    }
+}
+
+/// A slide definition, with an optional timeout (seconds) after which the slide
+/// is moved on from
+#[derive(Deserialize)]
+struct EntryDef {
+    #[serde(default)]
+    timeout: Option<u64>,
+    #[serde(flatten)]
+    slide: SlideDef,
+}
+
+/// Muses survey definition, slides are presented in order
+#[derive(Deserialize)]
+struct Survey {
+    slides: Vec<EntryDef>,
+}
+
+/// A slide ready to be presented
+pub struct Entry {
+    /// name of slide type
+    pub name: &'static str,
+    pub timeout: Option<Duration>,
+    pub slide: Box<dyn slide::Slide>,
+}
+
+/// load survey definition from path
+pub fn load(path: &str) -> Result<Vec<Entry>, String> {
+    let mut survey = String::new();
+    let mut f = File::open(path).map_err(|e| format!("unable to open survey file: {}", e))?;
+    f.read_to_string(&mut survey).map_err(|e| format!("unable to read survey file: {}", e))?;
+
+    let survey: Survey = serde_json::from_str(&survey).map_err(|e| format!("invalid survey file: {}", e))?;
+
+    survey.slides
+        .into_iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let name = e.slide.name();
+            let slide = e.slide.into_slide().map_err(|e| format!("slide {}: {}", i, e))?;
+            Ok(Entry {
+                name: name,
+                timeout: e.timeout.map(Duration::from_secs),
+                slide: slide,
+            })
+        })
+        .collect()
+}
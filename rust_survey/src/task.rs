@@ -0,0 +1,511 @@
+//! Continuous tracking tasks
+//!
+//! Copyright: Benedict R. Gaster
+//!
+//! A task maps touches on a material onto a 1D cursor, which the user keeps on
+//! a target. Tasks are parameterised by their input mapping, how targets are
+//! generated, what counts as success and how they are rendered on the client, so
+//! new tasks can be defined in the survey file. Press, slider and tap are presets.
+
+use rand::Rng;
+
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+use crate::msg;
+use crate::runtime;
+use crate::slide::{range, Outcome, Slide};
+use crate::world;
+use crate::ws_server;
+
+//-----------------------------------------------------------------------------
+// Definition
+//-----------------------------------------------------------------------------
+
+/// Touch value a cursor is driven by
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    Pressure,
+    X,
+    Y,
+}
+
+/// Mapping from a touch value, within input range, onto cursor position, within
+/// output range
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct InputMap {
+    pub axis: Axis,
+    pub input: (f32, f32),
+    pub output: (f32, f32),
+}
+
+impl InputMap {
+    fn cursor(&self, pressure: f32, x: f32, y: f32) -> f32 {
+        let value = match self.axis {
+            Axis::Pressure => pressure,
+            Axis::X => x,
+            Axis::Y => y,
+        };
+        range(self.output.0, self.output.1, self.input.0, self.input.1, value)
+    }
+}
+
+/// How targets are generated
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TargetGen {
+    /// stationary target, at a random position between min and max, a new target
+    /// is chosen after each success
+    Random { min: f32, max: f32 },
+    /// target of size moving back and forth between min and max, at speed units 
+    /// per second. While the cursor is on target it shrinks by shrink units per second,
+    /// down to min_size, and moves nudge units per second faster.
+    Bounce { min: f32, max: f32, size: f32, min_size: f32, speed: f32, shrink: f32, nudge: f32 },
+}
+
+/// Success criterion, cursor must stay on target for hold ms
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Success {
+    #[serde(default)]
+    pub hold: u64,
+}
+
+/// Client view a task is rendered on
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum View {
+    /// cursor is circle radius, target is ring radius
+    Press,
+    /// cursor is user box, target is box
+    Slider,
+    /// cursor is user box, target is arrow
+    Tap,
+}
+
+impl View {
+    fn goto(&self, material: u32) -> Vec<msg::Message> {
+        match self {
+            View::Press => vec![msg::materialIndex(material, msg::press_num()), msg::gotoPress()],
+            View::Slider => vec![msg::materialIndex(material, msg::slider_num()), msg::gotoSlider()],
+            View::Tap => vec![msg::materialIndex(material, msg::tap_num()), msg::gotoTap()],
+        }
+    }
+
+    fn render(&self, cursor: f32, target: &Target) -> msg::Message {
+        match self {
+            View::Press => msg::press(cursor, target.position),
+            View::Slider => msg::slider(cursor, target.position, target.size),
+            View::Tap => msg::tap(cursor, target.position),
+        }
+    }
+}
+
+/// Tracking task page of survey presentation
+#[derive(Deserialize)]
+pub struct Task {
+    /// name of task, as written to CSV
+    name: String,
+    /// material index
+    material: u32,
+    /// duration to test
+    duration: u64,
+    input: InputMap,
+    target: TargetGen,
+    /// size of cursor, 0 for a point
+    #[serde(default)]
+    cursor_size: f32,
+    /// cursor within tolerance of target is on target
+    #[serde(default)]
+    tolerance: f32,
+    /// if given, each success starts a new target and is recorded separately
+    #[serde(default)]
+    success: Option<Success>,
+    view: View,
+    /// record tracking error, once per frame
+    #[serde(default)]
+    tracking: bool,
+}
+
+impl Task {
+    pub fn new(
+        name: String,
+        material: u32,
+        duration: u64,
+        input: InputMap,
+        target: TargetGen,
+        cursor_size: f32,
+        tolerance: f32,
+        success: Option<Success>,
+        view: View,
+        tracking: bool) -> Self {
+        Task {
+            name: name,
+            material: material,
+            duration: duration,
+            input: input,
+            target: target,
+            cursor_size: cursor_size,
+            tolerance: tolerance,
+            success: success,
+            view: view,
+            tracking: tracking,
+        }
+    }
+
+    /// check definition is consistent
+    pub fn check(&self) -> Result<(), String> {
+        if self.duration == 0 {
+            return Err("task duration must be greater than 0".to_string());
+        }
+        if self.input.input.0 == self.input.input.1 {
+            return Err("task input range is empty".to_string());
+        }
+        match self.target {
+            TargetGen::Random { min, max } if min >= max => {
+                Err("task random target needs min less than max".to_string())
+            },
+            TargetGen::Bounce { min, max, size, min_size, .. } if size > max - min || min_size > size => {
+                Err("task bounce target needs min_size <= size <= max - min".to_string())
+            },
+            TargetGen::Bounce { speed, shrink, nudge, .. } if speed < 0.0 || shrink < 0.0 || nudge < 0.0 => {
+                Err("task bounce target needs speed, shrink and nudge of at least 0".to_string())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// is cursor on target
+    fn on_target(&self, cursor: f32, target: &Target) -> bool {
+        cursor >= target.position - self.tolerance &&
+        cursor + self.cursor_size <= target.position + target.size + self.tolerance
+    }
+
+}
+
+//-----------------------------------------------------------------------------
+// Running
+//-----------------------------------------------------------------------------
+
+/// Current target
+struct Target {
+    position: f32,
+    size: f32,
+    /// direction of travel, 1 or -1, for moving targets
+    direction: f32,
+}
+
+/// State of a running task
+struct TaskTrial<'a> {
+    task: &'a Task,
+    rng: rand::rngs::ThreadRng,
+    cursor: f32,
+    target: Target,
+    // cursor or target changed since last frame was rendered
+    changed: bool,
+    // time cursor came on target, for success criterion
+    hold_timer: Option<Instant>,
+    // success can fire, only rearmed once cursor leaves target
+    armed: bool,
+    // timer for time stamps outputs
+    overall_timer: Instant,
+    // details of each target and its contacts
+    details: Vec<(f32, f32)>,
+    data: Vec<world::Contacts>,
+    // tracking error, only measured once user has touched
+    tracking: metrics::Tracking,
+    touched: bool,
+    // time (seconds) cursor has been on target, sent to experimenters as it runs
+    time_on_target: f32,
+    live: runtime::LiveStats,
+}
+
+impl<'a> TaskTrial<'a> {
+    fn new(task: &'a Task) -> Self {
+        let mut trial = TaskTrial {
+            task: task,
+            rng: rand::thread_rng(),
+            cursor: task.input.output.0,
+            target: Target { position: 0.0, size: 0.0, direction: 0.0 },
+            changed: false,
+            hold_timer: None,
+            armed: true,
+            overall_timer: Instant::now(),
+            details: vec![],
+            data: vec![vec![]],
+            tracking: metrics::Tracking::new(),
+            touched: false,
+            time_on_target: 0.0,
+            live: runtime::LiveStats::new(),
+        };
+
+        trial.target = match task.target {
+            TargetGen::Random { min, max } => Target { position: trial.rng.gen_range(min, max), size: 0.0, direction: 0.0 },
+            TargetGen::Bounce { min, size, .. } => Target { position: min, size: size, direction: 1.0 },
+        };
+        trial.details.push(trial.detail());
+        trial
+    }
+
+    /// recorded for each target, cursor and target for stationary targets,
+    /// otherwise target position and size
+    fn detail(&self) -> (f32, f32) {
+        match self.task.target {
+            TargetGen::Random { .. } => (self.cursor, self.target.position),
+            TargetGen::Bounce { .. } => (self.target.position, self.target.size),
+        }
+    }
+}
+
+impl<'a> runtime::Handler for TaskTrial<'a> {
+    fn input(&mut self, input: msg::SenselMessage, _outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        let (_input_type, pressure, x, y, material, timestamp) = input;
+        if material != self.task.material {
+            return None;
+        }
+
+        self.cursor = self.task.input.cursor(pressure, x, y);
+        let current = self.data.len() - 1;
+        self.data[current].push((self.overall_timer.elapsed().as_millis(), pressure, x, y, timestamp));
+        self.touched = true;
+        self.changed = true;
+
+        if self.task.on_target(self.cursor, &self.target) {
+            if let Some(success) = self.task.success.filter(|_| self.armed) {
+                let timer = *self.hold_timer.get_or_insert(Instant::now());
+                if timer.elapsed().as_millis() >= success.hold as u128 {
+                    // one success per entry onto target
+                    self.hold_timer = None;
+                    self.armed = false;
+                    if let TargetGen::Random { min, max } = self.task.target {
+                        self.target.position = self.rng.gen_range(min, max);
+                    }
+
+                    // update target storage
+                    self.data.push(vec![]);
+                    self.details.push(self.detail());
+                }
+            }
+        }
+        else {
+            // need to make sure we reset hold if we fall out
+            self.hold_timer = None;
+            self.armed = true;
+        }
+
+        None
+    }
+
+    fn tick(&mut self, dt: f32, outbound_msg: &ws_server::WSServer) -> Option<Outcome> {
+        if self.overall_timer.elapsed().as_secs() >= self.task.duration {
+            return Some(Outcome::Completed);
+        }
+
+        if self.touched && self.task.on_target(self.cursor, &self.target) {
+            self.time_on_target = self.time_on_target + dt;
+        }
+        let time_on_target = self.time_on_target;
+        let elapsed = self.overall_timer.elapsed().as_secs_f32();
+        let targets = self.task.success.map(|_| self.details.len() - 1);
+        self.live.update(outbound_msg, || {
+            let mut stats = vec![("time on target".to_string(), format!("{:.1}s of {:.1}s", time_on_target, elapsed))];
+            if let Some(targets) = targets {
+                stats.push(("targets".to_string(), targets.to_string()));
+            }
+            stats
+        });
+
+        // update target and its direction, clamping within range and changing
+        // direction at boundary
+        if let TargetGen::Bounce { min, max, min_size, speed, shrink, nudge, .. } = self.task.target {
+            // moving targets get harder while the cursor is on them
+            let mut speed = speed;
+            if self.touched && self.task.on_target(self.cursor, &self.target) {
+                self.target.size = f32::max(self.target.size - shrink * dt, min_size);
+                speed = speed + nudge;
+            }
+
+            self.target.position = self.target.position + self.target.direction * speed * dt;
+            if self.target.position + self.target.size >= max {
+                self.target.direction = -1.0;
+                self.target.position = max - self.target.size;
+            }
+            else if self.target.position <= min {
+                self.target.direction = 1.0;
+                self.target.position = min;
+            }
+            self.changed = true;
+        }
+
+        // sample tracking error, once per frame, between centres of cursor and target
+        if self.task.tracking && self.touched {
+            let on_target = self.task.on_target(self.cursor, &self.target);
+            self.tracking.update(
+                self.cursor + self.task.cursor_size / 2.0,
+                self.target.position + self.target.size / 2.0,
+                on_target,
+                dt);
+        }
+        None
+    }
+
+    fn render(&mut self) -> Option<msg::Message> {
+        if self.changed {
+            self.changed = false;
+            return Some(self.task.view.render(self.cursor, &self.target));
+        }
+        None
+    }
+
+    fn paused(&mut self, duration: Duration) {
+        self.overall_timer = self.overall_timer + duration;
+        self.hold_timer = self.hold_timer.map(|timer| timer + duration);
+    }
+}
+
+impl Slide for Task {
+    fn run(&self,
+        world: &mut world::World,
+        events: &runtime::Events,
+        outbound_msg: &ws_server::WSServer,
+        deadline: Option<Instant>) -> Outcome {
+
+        // jump to task's page, with cursor and target in initial positions
+        for data in self.view.goto(self.material) {
+            outbound_msg.send(data);
+        }
+
+        let mut trial = TaskTrial::new(self);
+        outbound_msg.send(self.view.render(trial.cursor, &trial.target));
+
+        let mut clock = events.clock();
+        let outcome = runtime::run(&mut trial, events, outbound_msg, deadline, Some(&mut clock));
+
+        world.writeGesture(self.name.clone(), self.material, trial.details, trial.data);
+        if self.tracking {
+            world.writeTracking(self.name.clone(), self.material, &trial.tracking);
+        }
+        world.writeFrameTiming(self.name.clone(), self.material, &clock);
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use crate::runtime::Handler;
+
+    /// server for handlers to send to, on any free port
+    fn server() -> ws_server::WSServer {
+        let (inbound, _) = channel();
+        ws_server::WSServer::new("127.0.0.1:0".to_string(), inbound)
+    }
+
+    /// touch with pressure, which maps directly onto cursor
+    fn press(pressure: f32) -> msg::SenselMessage {
+        (msg::InputType::Move, pressure, 0.0, 0.0, 1, msg::Timestamp { sensor: None, received: 0.0 })
+    }
+
+    fn task(target: TargetGen, hold: u64) -> Task {
+        Task::new(
+            "test".to_string(),
+            1,
+            60,
+            InputMap { axis: Axis::Pressure, input: (0.0, 1000.0), output: (0.0, 1000.0) },
+            target,
+            0.0,
+            0.0,
+            Some(Success { hold: hold }),
+            View::Slider,
+            false)
+    }
+
+    fn bounce() -> TargetGen {
+        TargetGen::Bounce { min: 0.0, max: 1000.0, size: 100.0, min_size: 20.0, speed: 100.0, shrink: 10.0, nudge: 50.0 }
+    }
+
+    #[test]
+    fn task_hold_success() {
+        let server = server();
+        let task = task(bounce(), 50);
+        let mut trial = TaskTrial::new(&task);
+
+        trial.input(press(50.0), &server);
+        assert_eq!(trial.details.len(), 1);
+        thread::sleep(Duration::from_millis(60));
+        trial.input(press(50.0), &server);
+        assert_eq!(trial.details.len(), 2);
+        assert_eq!(trial.data.len(), 2);
+
+        // leaving target resets hold
+        trial.input(press(500.0), &server);
+        trial.input(press(50.0), &server);
+        trial.input(press(50.0), &server);
+        assert_eq!(trial.details.len(), 2);
+    }
+
+    #[test]
+    fn task_success_once_per_entry() {
+        let server = server();
+        let task = task(bounce(), 0);
+        let mut trial = TaskTrial::new(&task);
+
+        for _ in 0..5 {
+            trial.input(press(50.0), &server);
+        }
+        assert_eq!(trial.details.len(), 2);
+
+        // rearmed once cursor leaves target
+        trial.input(press(500.0), &server);
+        trial.input(press(50.0), &server);
+        trial.input(press(50.0), &server);
+        assert_eq!(trial.details.len(), 3);
+    }
+
+    #[test]
+    fn task_bounce_shrink_and_nudge() {
+        let server = server();
+        let task = task(bounce(), 1000);
+        let mut trial = TaskTrial::new(&task);
+
+        // untouched, target moves at speed
+        trial.tick(0.5, &server);
+        assert_eq!(trial.target.position, 50.0);
+        assert_eq!(trial.target.size, 100.0);
+
+        // on target, shrinks and moves faster
+        trial.input(press(100.0), &server);
+        trial.tick(0.5, &server);
+        assert_eq!(trial.target.position, 125.0);
+        assert_eq!(trial.target.size, 95.0);
+
+        // off target, moves at speed and keeps its size
+        trial.tick(0.5, &server);
+        assert_eq!(trial.target.position, 175.0);
+        assert_eq!(trial.target.size, 95.0);
+    }
+
+    #[test]
+    fn task_success_resets_random_target_only() {
+        let server = server();
+
+        let random = task(TargetGen::Random { min: 30.0, max: 100.0 }, 0);
+        let mut trial = TaskTrial::new(&random);
+        // cursor is rounded onto the half unit
+        trial.target.position = 50.5;
+        trial.input(press(50.0), &server);
+        assert_eq!(trial.details.len(), 2);
+        assert_ne!(trial.target.position, 50.5);
+        assert!(trial.target.position >= 30.0 && trial.target.position < 100.0);
+        assert_eq!(trial.details[1], (50.5, trial.target.position));
+
+        let bounce = task(bounce(), 0);
+        let mut trial = TaskTrial::new(&bounce);
+        trial.input(press(50.0), &server);
+        assert_eq!(trial.details.len(), 2);
+        assert_eq!(trial.target.position, 0.0);
+        assert_eq!(trial.details[1], (0.0, 100.0));
+    }
+}
@@ -0,0 +1,334 @@
+//! Terminal UI for running and monitoring a session
+//!
+//! Copyright: Benedict R. Gaster
+//!
+//! The terminal UI observes the same messages as an experimenter client, and
+//! sends control messages as if from an experimenter.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, Paragraph};
+use ratatui::Frame;
+
+use serde_json::Value;
+
+use crate::msg;
+
+//-----------------------------------------------------------------------------
+// Errors
+//-----------------------------------------------------------------------------
+
+/// number of recent errors kept
+const MAX_ERRORS: usize = 50;
+
+/// Logger keeping recent warnings and errors, so they can be shown rather than
+/// written over the terminal UI
+struct Logger {
+    errors: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let mut errors = self.errors.lock().unwrap();
+            if errors.len() == MAX_ERRORS {
+                errors.pop_front();
+            }
+            errors.push_back(format!("{} {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {
+    }
+}
+
+//-----------------------------------------------------------------------------
+// State
+//-----------------------------------------------------------------------------
+
+/// Session as seen by experimenter
+struct State {
+    participant: String,
+    /// slide name and its state, None if not yet run
+    slides: Vec<(String, Option<String>)>,
+    current: Option<usize>,
+    paused: bool,
+    /// messages per second from each material
+    rates: Vec<(u32, f32)>,
+    last_touch: Option<String>,
+    /// OSC messages that matched no input
+    unmatched: u64,
+    participants: u64,
+    experimenters: u64,
+    summary: Vec<(String, String)>,
+    /// results of current slide so far
+    live: Vec<(String, String)>,
+    ended: bool,
+}
+
+impl State {
+    /// update from a message sent to experimenters
+    fn update(&mut self, data: Value) {
+        let string = |v: &Value| v.as_str().unwrap_or("").to_string();
+
+        if msg::is_type("status", data.clone()) {
+            let index = data["index"].as_u64().unwrap_or(0) as usize;
+            // live results are only for the slide running
+            if self.current != Some(index) || data["state"] != "running" {
+                self.live = vec![];
+            }
+            self.participant = string(&data["participant"]);
+            if index < self.slides.len() {
+                self.slides[index].1 = Some(string(&data["state"]));
+            }
+            self.current = Some(index);
+            self.paused = data["paused"].as_bool().unwrap_or(false);
+        }
+        else if msg::is_type("inputActivity", data.clone()) {
+            self.rates = data["rates"]
+                .as_array()
+                .map(|rates| rates
+                    .iter()
+                    .map(|r| (r["material"].as_u64().unwrap_or(0) as u32, r["rate"].as_f64().unwrap_or(0.0) as f32))
+                    .collect())
+                .unwrap_or(vec![]);
+            self.unmatched = data["unmatched"].as_u64().unwrap_or(0);
+
+            let last = &data["last"];
+            if !last.is_null() {
+                self.last_touch = Some(format!(
+                    "{} material {} pressure {:.1} at ({:.1}, {:.1})",
+                    string(&last["input"]),
+                    last["material"],
+                    last["pressure"].as_f64().unwrap_or(0.0),
+                    last["x"].as_f64().unwrap_or(0.0),
+                    last["y"].as_f64().unwrap_or(0.0)));
+            }
+        }
+        else if msg::is_type("clients", data.clone()) {
+            self.participants = data["participants"].as_u64().unwrap_or(0);
+            self.experimenters = data["experimenters"].as_u64().unwrap_or(0);
+        }
+        else if msg::is_type("trialStats", data.clone()) {
+            self.summary = data["summary"]
+                .as_array()
+                .map(|summary| summary
+                    .iter()
+                    .map(|s| (string(&s["label"]), string(&s["value"])))
+                    .collect())
+                .unwrap_or(vec![]);
+        }
+        else if msg::is_type("liveStats", data.clone()) {
+            self.live = data["stats"]
+                .as_array()
+                .map(|stats| stats
+                    .iter()
+                    .map(|s| (string(&s["label"]), string(&s["value"])))
+                    .collect())
+                .unwrap_or(vec![]);
+        }
+        else if msg::is_type("sessionEnded", data) {
+            self.ended = true;
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Terminal UI
+//-----------------------------------------------------------------------------
+
+/// Running terminal UI
+pub struct Tui {
+    finished: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Tui {
+    /// how often the terminal is redrawn
+    const TICK: Duration = Duration::from_millis(100);
+
+    /// take over terminal, showing messages sent to experimenters, keys send control
+    /// messages to backend. Warnings and errors are logged to terminal UI from here on.
+    pub fn start(
+        participant: String,
+        slides: Vec<String>,
+        observe: Receiver<msg::Message>,
+        control: Sender<msg::Event>) -> Self {
+
+        let errors = Arc::new(Mutex::new(VecDeque::new()));
+        log::set_logger(Box::leak(Box::new(Logger { errors: errors.clone() })))
+            .expect("Unable to set logger");
+        log::set_max_level(LevelFilter::Warn);
+
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let mut state = State {
+            participant: participant,
+            slides: slides.into_iter().map(|name| (name, None)).collect(),
+            current: None,
+            paused: false,
+            rates: vec![],
+            last_touch: None,
+            unmatched: 0,
+            participants: 0,
+            experimenters: 0,
+            summary: vec![],
+            live: vec![],
+            ended: false,
+        };
+
+        let done = finished.clone();
+        let thread = thread::spawn(move || {
+            let mut terminal = ratatui::init();
+
+            loop {
+                while let Ok(data) = observe.try_recv() {
+                    state.update(data);
+                }
+
+                let finished = done.load(Ordering::SeqCst);
+                let errors: Vec<String> = errors.lock().unwrap().iter().cloned().collect();
+                terminal.draw(|frame| draw(frame, &state, &errors, finished)).expect("Unable to draw terminal UI");
+
+                if event::poll(Tui::TICK).unwrap_or(false) {
+                    match event::read() {
+                        Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                            let action = match key.code {
+                                KeyCode::Char('s') => Some(msg::Control::Skip),
+                                KeyCode::Char('r') => Some(msg::Control::Repeat),
+                                KeyCode::Char('a') => Some(msg::Control::Abort),
+                                KeyCode::Char('p') => Some(if state.paused { msg::Control::Resume } else { msg::Control::Pause }),
+                                KeyCode::Char('q') if finished => break,
+                                _ => None,
+                            };
+
+                            if let Some(action) = action {
+                                if !finished {
+                                    control.send(msg::Event::Message(msg::controlAction(action))).ok();
+                                }
+                            }
+                        },
+                        _ => {},
+                    }
+                }
+            }
+
+            ratatui::restore();
+        });
+
+        Tui {
+            finished: finished,
+            thread: thread,
+        }
+    }
+
+    /// session is over, wait for experimenter to quit
+    pub fn finish(self) {
+        self.finished.store(true, Ordering::SeqCst);
+        self.thread.join().ok();
+    }
+}
+
+fn draw(frame: &mut Frame, state: &State, errors: &Vec<String>, finished: bool) {
+    let [header, body, errors_area, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(8),
+        Constraint::Length(8),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    let [slides_area, right] = Layout::horizontal([
+        Constraint::Percentage(50),
+        Constraint::Percentage(50),
+    ]).areas(body);
+
+    let [input_area, results_area] = Layout::vertical([
+        Constraint::Percentage(50),
+        Constraint::Percentage(50),
+    ]).areas(right);
+
+    // participant and client state
+    let paused = if state.paused { "  [PAUSED]" } else { "" };
+    frame.render_widget(
+        Paragraph::new(format!(
+            "Participant {}   Participant clients {}   Experimenter clients {}{}",
+            state.participant, state.participants, state.experimenters, paused))
+            .block(Block::bordered().title("Muses Survey")),
+        header);
+
+    // slide list, with progress
+    let completed = state.slides.iter().filter(|(_, s)| s.is_some()).count();
+    let slides: Vec<ListItem> = state.slides
+        .iter()
+        .enumerate()
+        .map(|(i, (name, slide_state))| {
+            let marker = if state.current == Some(i) { ">" } else { " " };
+            let item = ListItem::new(format!(
+                "{} {:>2} {:<22} {}", marker, i, name, slide_state.as_ref().map(|s| s.as_str()).unwrap_or("")));
+            if state.current == Some(i) {
+                item.style(Style::default().add_modifier(Modifier::BOLD))
+            }
+            else {
+                item
+            }
+        })
+        .collect();
+    frame.render_widget(
+        List::new(slides)
+            .block(Block::bordered().title(format!("Slides ({}/{})", completed, state.slides.len()))),
+        slides_area);
+
+    // OSC input
+    let mut input: Vec<Line> = state.rates
+        .iter()
+        .map(|(material, rate)| Line::from(format!("Material {}: {:.1} msgs/s", material, rate)))
+        .collect();
+    input.push(Line::from(format!("Last touch: {}", state.last_touch.as_ref().map(|s| s.as_str()).unwrap_or("-"))));
+    input.push(Line::from(format!("Unmatched: {}", state.unmatched)));
+    frame.render_widget(Paragraph::new(input).block(Block::bordered().title("OSC input")), input_area);
+
+    // results so far, followed by those of current slide
+    let mut results: Vec<Line> = state.summary
+        .iter()
+        .map(|(label, value)| Line::from(format!("{}: {}", label, value)))
+        .collect();
+    if !state.live.is_empty() {
+        results.push(Line::from("Current slide:"));
+        results.extend(state.live
+            .iter()
+            .map(|(label, value)| Line::from(format!("  {}: {}", label, value))));
+    }
+    frame.render_widget(Paragraph::new(results).block(Block::bordered().title("Results")), results_area);
+
+    // most recent errors, that fit
+    let rows = errors_area.height.saturating_sub(2) as usize;
+    let recent: Vec<Line> = errors
+        .iter()
+        .skip(errors.len().saturating_sub(rows))
+        .map(|e| Line::from(e.as_str()))
+        .collect();
+    frame.render_widget(Paragraph::new(recent).block(Block::bordered().title("Recent errors")), errors_area);
+
+    let keys = if finished || state.ended {
+        "Session ended, q quit"
+    }
+    else {
+        "s skip   p pause/resume   r restart slide   a abort session"
+    };
+    frame.render_widget(Paragraph::new(keys), footer);
+}
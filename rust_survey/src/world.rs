@@ -7,43 +7,78 @@
 extern crate uuid;
 use uuid::Uuid;
 
-use std::fs::File;
+use std::fs::{remove_file, write, File};
+use std::io::{sink, ErrorKind, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
 
 extern crate csv;
 
 use crate::msg;
+use crate::metrics;
+use crate::runtime;
 
-pub type Contacts = Vec<(u128, f32, f32, f32)>;
+/// timestamp (ms since start of trial), pressure, x, y, and when sampled and received
+pub type Contacts = Vec<(u128, f32, f32, f32, msg::Timestamp)>;
 
 pub struct PressData {
     /// radius of circle at start
     pub circle_radius: f32,
     /// radius of ring
     pub ring_radius: f32,
-    /// contacts record timestamp, pressure, x, and y
+    /// contacts record timestamp, pressure, x, y, and when sampled and received
     pub contacts: Contacts,
     /// did the user complete the press test for given params
     pub success: bool,
 }
 
+/// Circular target on the 2D canvas, position is its centre
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Target {
+    pub x: f32,
+    pub y: f32,
+    /// diameter of target
+    pub size: f32,
+}
+
 ///
 pub struct World {
     pub id: Uuid,
     /// likert CSV file
-    pub csv: csv::Writer<File>,
+    pub csv: csv::Writer<Box<dyn Write>>,
+    /// participant metadata, e.g. demographics, written as JSON
+    pub metadata: Map<String, Value>,
+    /// path of likert CSV file
+    likert_path: String,
+    /// path of participant metadata file
+    metadata_path: String,
+    /// session has ended early, e.g. consent was declined, and no further slides should be run
+    pub ended: bool,
+    /// (label, value) summary of participant's results, which can be shown at end of session
+    pub summary: Vec<(String, String)>,
 }
 
 impl World {
-    pub fn new(id: Uuid, likert_file: File) -> Self {
+    pub fn new(id: Uuid, likert_file: File, likert_path: String, metadata_path: String) -> Self {
 
-        // handle Likert CSV files, including writing column headings
-        let mut csv = csv::Writer::from_writer(likert_file);
-        csv.write_record(&["ID", "Category", "Gesture", "Material", "Feeling", "Answer"]);
+        // handle Likert CSV files, including writing column headings. Rows differ in 
+        // length depending on what they record, so the writer must be flexible
+        let mut csv = csv::WriterBuilder::new().flexible(true).from_writer(Box::new(likert_file) as Box<dyn Write>);
+        csv.write_record(&["ID", "Category", "Gesture", "Material", "Feeling", "Answer", "Latency"]);
         csv.flush().unwrap();
         
+        let mut metadata = Map::new();
+        metadata.insert("id".to_string(), json!(id.to_hyphenated().to_string()));
+
         World {
             id: id,
             csv: csv,
+            metadata: metadata,
+            likert_path: likert_path,
+            metadata_path: metadata_path,
+            ended: false,
+            summary: vec![],
         }
     }
 
@@ -52,16 +87,39 @@ impl World {
         self.id.to_hyphenated().to_string()
     }
 
-    /// write likert data to CSV
-    pub fn writeLikert(&mut self, gesture: &str, material: &str, likert: msg::Likert) {
-        let categories = ["Strongly Disagree", "Disagree", "Netural", "Agree", "Strongly Agree"];
+    /// remove all data written for participant and end session
+    pub fn discard(&mut self) {
+        // close CSV file before removing it, anything written from here on is dropped
+        self.csv = csv::WriterBuilder::new().flexible(true).from_writer(Box::new(sink()) as Box<dyn Write>);
 
+        for path in &[&self.likert_path, &self.metadata_path] {
+            match remove_file(path) {
+                Ok(()) => info!("removed {}", path),
+                Err(ref e) if e.kind() == ErrorKind::NotFound => {},
+                Err(e) => error!("unable to remove {}: {}", path, e),
+            }
+        }
+        self.metadata.clear();
+        self.ended = true;
+    }
+
+    /// add an entry to participant metadata, which is written out immediately
+    pub fn writeMetadata(&mut self, name: &str, value: Value) {
+        self.metadata.insert(name.to_string(), value);
+        let j = serde_json::to_string_pretty(&self.metadata).unwrap();
+        write(&self.metadata_path, j).expect("Unable to write participant metadata");
+    }
+
+    /// write likert data to CSV, category is the label of the answer on the scale. Latency (ms) 
+    /// is from the slide being shown to the answer being given.
+    pub fn writeLikert(&mut self, gesture: &str, material: &str, likert: msg::Likert, category: &str, latency: u128) {
         self.csv.write_record(&[
             &self.create_id(), 
-            categories[(likert.value-1) as usize],
+            category,
             gesture, material, 
             &likert.name, 
-            &likert.value.to_string()]).unwrap();
+            &likert.value.to_string(),
+            &latency.to_string()]).unwrap();
         self.csv.flush().unwrap();
     }
 
@@ -73,6 +131,62 @@ impl World {
         self.csv.flush().unwrap();
     }
 
+    /// write a pairwise comparison out to CSV
+    pub fn writePairwise(&mut self, attribute: &str, trial: u32, left: u32, right: u32, choice: u32, time: u128) {
+        // id=id, pairwise, attribute, trial, left, right, choice, timeMs
+        self.csv.write_record(&[
+            self.create_id(),
+            "pairwise".to_string(),
+            attribute.to_string(),
+            trial.to_string(),
+            left.to_string(),
+            right.to_string(),
+            choice.to_string(),
+            time.to_string()]).unwrap();
+        self.csv.flush().unwrap();
+    }
+
+    /// write NASA-TLX ratings for each subscale, and weights if present, out to CSV. Raw TLX 
+    /// is the mean of ratings and weighted TLX the mean of ratings weighted by the number of 
+    /// times each subscale was chosen in the pairwise comparisons.
+    pub fn writeTlx(&mut self, gesture: &str, material: u32, ratings: &Vec<u32>, weights: Option<&Vec<u32>>) {
+        let workload = metrics::Workload::new(ratings, weights);
+
+        // id=id, tlx, gesture, material, raw, weighted, ratings (mental, physical, temporal, 
+        // performance, effort, frustration), weights (in same order) 
+        let mut out = vec![
+            self.create_id(),
+            "tlx".to_string(),
+            gesture.to_string(),
+            material.to_string(),
+            workload.raw.to_string(),
+            workload.weighted.map(|w| w.to_string()).unwrap_or_default()];
+        out.extend(ratings.iter().map(|r| r.to_string()));
+        match weights {
+            Some(weights) => out.extend(weights.iter().map(|w| w.to_string())),
+            None => out.extend(ratings.iter().map(|_| "".to_string())),
+        }
+
+        self.csv.write_record(out).unwrap();
+        self.csv.flush().unwrap();
+
+        self.summary.push((
+            format!("{} on material {}, workload", gesture, material),
+            format!("{:.0} / 100", workload.raw)));
+    }
+
+    /// contact formatted for CSV, sensor time is empty if touch was not timestamped by sensor
+    fn contact(contact: &(u128, f32, f32, f32, msg::Timestamp)) -> String {
+        format!(
+            "({} - {} - {} - {} - {} - {})",
+            contact.0.to_string(),
+            contact.1.to_string(),
+            contact.2.to_string(),
+            contact.3.to_string(),
+            contact.4.sensor.map_or("".to_string(), |t| t.to_string()),
+            contact.4.received.to_string())
+    }
+
     /// write gesture data out to CSV
     pub fn writeGesture(
         &mut self,
@@ -82,7 +196,7 @@ impl World {
         contacts: Vec<Contacts>) {
         
         for i in 0..circle_ring_radius.len() {
-            // id=id, press, material, circleRadius, ringRadius, (timestamp, pressure, x, y), ...
+            // id=id, press, material, circleRadius, ringRadius, (timestamp, pressure, x, y, sensorMs, receivedMs), ...
             let mut out = vec![
                 self.create_id(), 
                 name.clone(), 
@@ -91,12 +205,7 @@ impl World {
                 circle_ring_radius[i].1.to_string()];
             
             for contact in &contacts[i] {
-                out.push(format!(
-                    "({} - {} - {} - {})", 
-                    contact.0.to_string(), 
-                    contact.1.to_string(), 
-                    contact.2.to_string(),
-                    contact.3.to_string()));
+                out.push(World::contact(contact));
             }
 
             self.csv.write_record(out);
@@ -104,8 +213,280 @@ impl World {
         }
     }
 
+    /// write target acquisition data out to CSV, acquisition time is None for 
+    /// targets not acquired
+    pub fn writeAcquisition(
+        &mut self,
+        name: String,
+        material: u32,
+        targets: Vec<(Target, Option<u128>)>,
+        contacts: Vec<Contacts>) {
+
+        for i in 0..targets.len() {
+            // id=id, name, material, targetX, targetY, targetSize, acquisitionMs, (timestamp, pressure, x, y, sensorMs, receivedMs), ...
+            let (target, time) = targets[i];
+            let mut out = vec![
+                self.create_id(),
+                name.clone(),
+                material.to_string(),
+                target.x.to_string(),
+                target.y.to_string(),
+                target.size.to_string(),
+                time.map_or("".to_string(), |t| t.to_string())];
+
+            for contact in &contacts[i] {
+                out.push(World::contact(contact));
+            }
+
+            self.csv.write_record(out).unwrap();
+        }
+        self.csv.flush().unwrap();
+    }
+
+    /// write 2D target tracking out to CSV, target moves through waypoints at speed
+    pub fn writeTargetTrack(
+        &mut self,
+        name: String,
+        material: u32,
+        speed: f32,
+        waypoints: &Vec<Target>,
+        contacts: &Contacts) {
+
+        // id=id, name_track, material, speed, waypoints, (timestamp, pressure, x, y, sensorMs, receivedMs), ...
+        let mut out = vec![
+            self.create_id(),
+            format!("{}_track", name),
+            material.to_string(),
+            speed.to_string(),
+            waypoints.len().to_string()];
+
+        for contact in contacts {
+            out.push(World::contact(contact));
+        }
+
+        self.csv.write_record(out).unwrap();
+        self.csv.flush().unwrap();
+    }
+
+    /// write Fitts' law selections, followed by per condition and overall summaries, out to CSV
+    pub fn writeFitts(
+        &mut self,
+        material: u32,
+        selections: &Vec<metrics::FittsSelection>,
+        summaries: &Vec<((f32, f32), metrics::FittsSummary)>) {
+
+        for s in selections {
+            // id=id, fitts, material, distance, width, fromX, fromY, targetX, targetY, endX, endY, timeMs, hit
+            self.csv.write_record(&[
+                self.create_id(),
+                "fitts".to_string(),
+                material.to_string(),
+                s.trial.distance.to_string(),
+                s.trial.width.to_string(),
+                s.from.0.to_string(),
+                s.from.1.to_string(),
+                s.target.0.to_string(),
+                s.target.1.to_string(),
+                s.endpoint.0.to_string(),
+                s.endpoint.1.to_string(),
+                s.trial.time.to_string(),
+                s.trial.hit.to_string()]).unwrap();
+        }
+
+        let mut summary_rows: Vec<(String, String, metrics::FittsSummary)> = summaries
+            .iter()
+            .map(|((distance, width), summary)| (distance.to_string(), width.to_string(), *summary))
+            .collect();
+
+        // throughput for material is the mean of throughput for each condition
+        let measured: Vec<&metrics::FittsSummary> = summaries.iter().map(|s| &s.1).filter(|s| s.trials > 0).collect();
+        if measured.len() > 0 {
+            let n = measured.len() as f32;
+            let mean = |f: &dyn Fn(&metrics::FittsSummary) -> f32| measured.iter().map(|s| f(s)).sum::<f32>() / n;
+            summary_rows.push(("all".to_string(), "all".to_string(), metrics::FittsSummary {
+                trials: measured.iter().map(|s| s.trials).sum(),
+                error_rate: mean(&|s| s.error_rate),
+                effective_distance: mean(&|s| s.effective_distance),
+                effective_width: mean(&|s| s.effective_width),
+                effective_id: mean(&|s| s.effective_id),
+                movement_time: mean(&|s| s.movement_time),
+                throughput: mean(&|s| s.throughput),
+            }));
+        }
+
+        if let Some((_, _, all)) = summary_rows.iter().find(|row| row.0 == "all") {
+            self.summary.push((
+                format!("pointing on material {}, throughput", material),
+                format!("{:.2} bits/s", all.throughput)));
+        }
+
+        for (distance, width, summary) in summary_rows {
+            // id=id, fitts_summary, material, distance, width, selections, errorRate, De, We, IDe, MT, TP
+            self.csv.write_record(&[
+                self.create_id(),
+                "fitts_summary".to_string(),
+                material.to_string(),
+                distance,
+                width,
+                summary.trials.to_string(),
+                summary.error_rate.to_string(),
+                summary.effective_distance.to_string(),
+                summary.effective_width.to_string(),
+                summary.effective_id.to_string(),
+                summary.movement_time.to_string(),
+                summary.throughput.to_string()]).unwrap();
+        }
+        self.csv.flush().unwrap();
+    }
+
+    /// write per frame target and user values, for a continuous gesture trial, out to CSV
+    pub fn writeFrames(&mut self, name: String, material: u32, frames: &Vec<(u128, f32, f32)>) {
+        // id=id, name_frames, material, (timestamp - target - user), ...
+        let mut out = vec![
+            self.create_id(),
+            format!("{}_frames", name),
+            material.to_string()];
+
+        for frame in frames {
+            out.push(format!(
+                "({} - {} - {})",
+                frame.0.to_string(),
+                frame.1.to_string(),
+                frame.2.to_string()));
+        }
+
+        self.csv.write_record(out).unwrap();
+        self.csv.flush().unwrap();
+    }
+
+    /// write frame interval statistics, and latency of frames drawn, for an animated trial out to CSV
+    pub fn writeFrameTiming(&mut self, name: String, material: u32, clock: &runtime::Clock) {
+        info!(
+            "{} on material {}: {} frames, mean interval {:.2}ms (nominal {:.2}ms), jitter {:.2}ms, {} missed",
+            name, material, clock.frames(), clock.mean_ms(), clock.interval_ms(), clock.jitter_ms(), clock.missed());
+
+        // id=id, name_timing, material, frames, nominal interval (ms), mean interval (ms), jitter (ms), missed frames
+        self.csv.write_record(&[
+            self.create_id(),
+            format!("{}_timing", name),
+            material.to_string(),
+            clock.frames().to_string(),
+            clock.interval_ms().to_string(),
+            clock.mean_ms().to_string(),
+            clock.jitter_ms().to_string(),
+            clock.missed().to_string()]).unwrap();
+
+        let latency = clock.latency();
+        info!(
+            "{} on material {}: {} of {} frames drawn, round trip {:.1}ms, input to display {:.1}ms (max {:.1}ms)",
+            name, material, latency.frames_drawn(), latency.frames_sent(), latency.round_trip_ms(),
+            latency.input_to_display_ms(), latency.max_input_to_display_ms());
+
+        // id=id, name_latency, material, frames sent, frames drawn, round trip (ms), input to display (ms), max input to display (ms)
+        self.csv.write_record(&[
+            self.create_id(),
+            format!("{}_latency", name),
+            material.to_string(),
+            latency.frames_sent().to_string(),
+            latency.frames_drawn().to_string(),
+            latency.round_trip_ms().to_string(),
+            latency.input_to_display_ms().to_string(),
+            latency.max_input_to_display_ms().to_string()]).unwrap();
+        self.csv.flush().unwrap();
+    }
+
+    /// write tracking metrics for a continuous gesture trial out to CSV
+    pub fn writeTracking(&mut self, name: String, material: u32, tracking: &metrics::Tracking) {
+        // id=id, name_tracking, material, rms, mean absolute error, % on target, lag (ms), exits
+        self.csv.write_record(&[
+            self.create_id(),
+            format!("{}_tracking", name),
+            material.to_string(),
+            tracking.rms().to_string(),
+            tracking.mean_abs().to_string(),
+            tracking.percent_on_target().to_string(),
+            tracking.lag_ms().to_string(),
+            tracking.exits().to_string()]).unwrap();
+        self.csv.flush().unwrap();
+
+        self.summary.push((
+            format!("{} on material {}, time on target", name, material),
+            format!("{:.0}%", tracking.percent_on_target())));
+    }
+
+    /// write steadiness metrics for a hold trial out to CSV, completed is if hold 
+    /// was achieved, otherwise steadiness is of a partial hold
+    pub fn writeSteadiness(&mut self, name: String, material: u32, target: f32, completed: bool, steadiness: &metrics::Steadiness) {
+        // id=id, name_steadiness, material, target, completed, samples, mean absolute error, coefficient of variation, drift
+        self.csv.write_record(&[
+            self.create_id(),
+            format!("{}_steadiness", name),
+            material.to_string(),
+            target.to_string(),
+            completed.to_string(),
+            steadiness.samples.to_string(),
+            steadiness.mean_error.to_string(),
+            steadiness.cv.to_string(),
+            steadiness.drift.to_string()]).unwrap();
+        self.csv.flush().unwrap();
+
+        self.summary.push((
+            format!("{} on material {}, mean pressure error", name, material),
+            format!("{:.1}", steadiness.mean_error)));
+    }
+
+    /// mark session as complete, in both CSV and participant metadata
+    pub fn writeComplete(&mut self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+
+        // id=id, session, complete, timestamp
+        self.csv.write_record(&[
+            self.create_id(),
+            "session".to_string(),
+            "complete".to_string(),
+            timestamp.to_string()]).unwrap();
+        self.csv.flush().unwrap();
+
+        self.writeMetadata("complete", json!(true));
+        self.writeMetadata("completed_at", json!(timestamp));
+    }
+
+    /// write how a slide finished, index is position of slide in survey and 
+    /// duration (ms) is time slide was running for
+    pub fn writeOutcome(&mut self, index: usize, name: &str, outcome: &str, duration: u128) {
+        // id=id, slide, index, name, outcome, duration
+        self.csv.write_record(&[
+            self.create_id(),
+            "slide".to_string(),
+            index.to_string(),
+            name.to_string(),
+            outcome.to_string(),
+            duration.to_string()]).unwrap();
+        self.csv.flush().unwrap();
+    }
+
     /// In some cases, when in a loop, for example, we don't want to flush until end of slide.
     pub fn flush_CSV(&mut self) {
         self.csv.flush().unwrap();
     }
-}
\ No newline at end of file
+}
+
+/// withdraw a participant, deleting all of their data, returning the files removed
+pub fn withdraw(likert_dir: &str, participants_dir: &str, id: &str) -> Result<Vec<String>, String> {
+    // only accept a valid ID, so that nothing outside of data directories can be removed
+    let id = Uuid::parse_str(id).map_err(|_| format!("'{}' is not a valid participant ID", id))?;
+    let id = id.to_hyphenated().to_string();
+
+    let mut removed = vec![];
+    for path in vec![format!("{}{}.csv", likert_dir, id), format!("{}{}.json", participants_dir, id)] {
+        match remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {},
+            Err(e) => return Err(format!("unable to remove {}: {}", path, e)),
+        }
+    }
+    Ok(removed)
+}
@@ -8,18 +8,69 @@ extern crate ws;
 
 use std::thread;
 use std::sync::mpsc::channel;
-use std::io::stdin;
 
 use ws::{Factory, Handler};
 
 use std::sync::mpsc::{Sender, Receiver};
+use std::sync::{Arc, Mutex};
 use serde_json::Value;
 
 use crate::msg::*;
 
+/// Connected clients, by role
+struct Clients {
+    participants: Vec<ws::Sender>,
+    experimenters: Vec<ws::Sender>,
+    /// local observers (e.g. terminal UI), sent the same messages as experimenters
+    observers: Vec<Sender<Message>>,
+    /// last status sent to experimenters, so that an experimenter connecting
+    /// mid-session is brought up to date
+    status: Option<Message>,
+}
+
+impl Clients {
+    fn remove(&mut self, id: u32) {
+        self.participants.retain(|c| c.connection_id() != id);
+        self.experimenters.retain(|c| c.connection_id() != id);
+        self.connections_changed();
+    }
+
+    /// send JSON data to experimenters and observers
+    fn send_experimenters(&mut self, data: Message) {
+        if is_type("status", data.clone()) {
+            self.status = Some(data.clone());
+        }
+        send_all(&self.experimenters, &data);
+        self.observers.retain(|o| o.send(data.clone()).is_ok());
+    }
+
+    fn connections_changed(&mut self) {
+        let data = clients(self.participants.len(), self.experimenters.len());
+        self.send_experimenters(data);
+    }
+}
+
+/// send JSON data to each client
+fn send_all(clients: &Vec<ws::Sender>, data: &Message) {
+    // in general this should not fail, but just in case...
+    match serde_json::to_string(data) {
+        Ok(s) => {
+            for client in clients {
+                client.send(s.clone());
+            }
+        },
+        Err(_) => {
+
+        }
+    }
+}
+
 struct ServerHandler {
     ws: ws::Sender,
-    inbound: Sender<Message>,
+    inbound: Sender<Event>,
+    clients: Arc<Mutex<Clients>>,
+    /// role of client, unknown until its connected message is received
+    role: Option<Role>,
 }
 
 impl Handler for ServerHandler {
@@ -32,8 +83,51 @@ impl Handler for ServerHandler {
             ws::Message::Text(data) => {
                 let v: Value = serde_json::from_str(&data).unwrap();
                 info!("message received from client: {}", v.clone());
-                // send message to main app
-                self.inbound.send(v);
+
+                match self.role {
+                    None => {
+                        // first message must be connected, giving client's role
+                        match role(v.clone()) {
+                            Ok(Role::Participant) => {
+                                self.role = Some(Role::Participant);
+                                let mut clients = self.clients.lock().unwrap();
+                                clients.participants.push(self.ws.clone());
+                                clients.connections_changed();
+                                // send message to main app, so it knows protocol has begun
+                                self.inbound.send(Event::Message(v));
+                            },
+                            Ok(Role::Experimenter) => {
+                                self.role = Some(Role::Experimenter);
+                                let mut clients = self.clients.lock().unwrap();
+                                clients.experimenters.push(self.ws.clone());
+                                if let Some(status) = &clients.status {
+                                    send_all(&vec![self.ws.clone()], status);
+                                }
+                                clients.connections_changed();
+                            },
+                            Err(_) => {
+                                error!("expected connected message, found {}", v);
+                            },
+                        }
+                    },
+                    Some(Role::Participant) => {
+                        // only experimenter can control session
+                        if control(v.clone()).is_ok() {
+                            error!("control message from participant ignored");
+                        }
+                        else {
+                            self.inbound.send(Event::Message(v));
+                        }
+                    },
+                    Some(Role::Experimenter) => {
+                        if control(v.clone()).is_ok() {
+                            self.inbound.send(Event::Message(v));
+                        }
+                        else {
+                            error!("unexpected message from experimenter {}", v);
+                        }
+                    },
+                }
             },
             _ => {
 
@@ -42,10 +136,15 @@ impl Handler for ServerHandler {
 
         Ok(())
     }
+
+    fn on_close(&mut self, _code: ws::CloseCode, _reason: &str) {
+        self.clients.lock().unwrap().remove(self.ws.connection_id());
+    }
 }
 
 struct ServerFactory {
-    inbound: Sender<Message>,
+    inbound: Sender<Event>,
+    clients: Arc<Mutex<Clients>>,
 }
 
 impl Factory for ServerFactory {
@@ -56,26 +155,66 @@ impl Factory for ServerFactory {
             ws: ws,
             // default to server
             inbound: self.inbound.clone(),
+            clients: self.clients.clone(),
+            role: None,
         }
     }
 }
 
+/// Handle for sending to experimenters, which can be shared with other threads
+#[derive(Clone)]
+pub struct Monitor {
+    clients: Arc<Mutex<Clients>>,
+}
+
+impl Monitor {
+    /// send JSON data to experimenters
+    pub fn send(&self, data: Message) {
+        self.clients.lock().unwrap().send_experimenters(data);
+    }
+
+    /// resend last status to experimenters, marked as paused or not
+    pub fn paused(&self, paused: bool) {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(mut status) = clients.status.clone() {
+            status["paused"] = Value::Bool(paused);
+            clients.send_experimenters(status);
+        }
+    }
+
+    /// receive a copy of all messages sent to experimenters
+    pub fn observe(&self) -> Receiver<Message> {
+        let (sender, receiver) = channel();
+        self.clients.lock().unwrap().observers.push(sender);
+        receiver
+    }
+}
+
 pub struct WSServer {
     //socket : ws::WebSocket<ServerFactory>,
     handle: ws::Sender,
     listening_thread: thread::JoinHandle<()>,
+    clients: Arc<Mutex<Clients>>,
 }
 
 impl WSServer {
     pub fn new(
         address: String, 
-        inbound:Sender<Message>) -> Self {
+        inbound:Sender<Event>) -> Self {
+
+        let clients = Arc::new(Mutex::new(Clients {
+            participants: vec![],
+            experimenters: vec![],
+            observers: vec![],
+            status: None,
+        }));
 
         let socket : ws::WebSocket<ServerFactory> = 
             ws::Builder::new()
             .build(ServerFactory {
                 //tx: tx,
                 inbound: inbound,
+                clients: clients.clone(),
         }).unwrap();
 
         // get a handle to sender so we can send messages outbound
@@ -89,6 +228,7 @@ impl WSServer {
         WSServer {
             handle: handle,
             listening_thread: ws_thread, 
+            clients: clients,
         }
     }
 
@@ -96,41 +236,23 @@ impl WSServer {
         &self.handle
     } 
 
-    /// send JSON data to socket
+    /// send JSON data to participant, experimenters are sent a copy so they can 
+    /// follow the session
     pub fn send(&self, data: Message) {
-        // in general this should not fail, but just in case...
-        match serde_json::to_string(&data) {
-            Ok(s) => {
-                self.handle.send(s);
-            },
-            Err(_) => {
+        let mut clients = self.clients.lock().unwrap();
+        send_all(&clients.participants, &data);
+        clients.send_experimenters(participant(data));
+    }
 
-            }
+    /// send JSON data to experimenters
+    pub fn send_experimenter(&self, data: Message) {
+        self.monitor().send(data);
+    }
+
+    /// handle for sending to experimenters from other threads
+    pub fn monitor(&self) -> Monitor {
+        Monitor {
+            clients: self.clients.clone(),
         }
     }
 }
-
-// pub fn run(
-//     address: String, 
-//     inbound:Sender<Message>, 
-//     receive_incoming_msgs: Receiver<Message>) {
-
-//     //let (tx, rx) = channel();
-//     let socket : ws::WebSocket<ServerFactory> = 
-//         ws::Builder::new()
-//         .build(ServerFactory {
-//             //tx: tx,
-//             inbound: inbound,
-//     }).unwrap();
-
-//     let handle = socket.broadcaster();
-
-//     // we need a thread to handle incomming events
-//     let ws_thread : thread::JoinHandle<()> = thread::spawn(move || {
-//         socket.listen(address).unwrap();
-//     });
-
-//     println!("Press Enter to exit survey backend");
-//     let mut input = String::new();
-//     stdin().read_line(&mut input).unwrap(); 
-// }